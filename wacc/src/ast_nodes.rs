@@ -9,22 +9,43 @@
 //!   - Their super modules are private, so you cannot specify which `ast_node_variants` to use;
 //!   - `ast_node_variants` modules are not marked as `pub(super)` because doing so prevents LSP autocompletion (like `asm/c::Iden...` won't work);
 
+// The ambiguity of `ast_node_variants` described above is deliberate.
+#![allow(ambiguous_glob_reexports)]
+
 mod asm_nodes;
+mod aarch64_nodes;
 mod c_nodes;
+mod riscv64_nodes;
 mod tacky_nodes;
+mod wasm_nodes;
 
 pub use asm_nodes::*;
+pub use aarch64_nodes::*;
 pub use c_nodes::*;
+pub use riscv64_nodes::*;
 pub use tacky_nodes::*;
+pub use wasm_nodes::*;
 
 pub mod asm {
     pub use super::asm_nodes::ast_node_variants::*;
 }
 
+pub mod aarch64 {
+    pub use super::aarch64_nodes::ast_node_variants::*;
+}
+
 pub mod c {
     pub use super::c_nodes::ast_node_variants::*;
 }
 
+pub mod riscv64 {
+    pub use super::riscv64_nodes::ast_node_variants::*;
+}
+
 pub mod tacky {
     pub use super::tacky_nodes::ast_node_variants::*;
 }
+
+pub mod wasm {
+    pub use super::wasm_nodes::ast_node_variants::*;
+}
@@ -0,0 +1,58 @@
+use crate::frame::FrameLayout;
+
+pub mod ast_node_variants {
+    pub use super::Aarch64Program::*;
+    pub use super::Aarch64FunctionDefinition::*;
+    pub use super::Aarch64Identifier::*;
+    pub use super::Aarch64Instruction::*;
+    pub use super::Aarch64UnaryOperator::*;
+    pub use super::Aarch64Operand::*;
+    pub use super::Aarch64Reg::*;
+}
+
+#[derive(Debug, Clone)]
+pub enum Aarch64Program {
+    Program(Aarch64FunctionDefinition),
+}
+
+#[derive(Debug, Clone)]
+pub enum Aarch64FunctionDefinition {
+    Function(Aarch64Identifier, Vec<Aarch64Instruction>, FrameLayout),
+}
+
+#[derive(Debug, Clone)]
+pub enum Aarch64Identifier {
+    Identifier(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Aarch64Instruction {
+    Mov(Aarch64Operand, Aarch64Operand),
+    Unary(Aarch64UnaryOperator, Aarch64Operand, Aarch64Operand),
+    Movz(Aarch64Reg, u16, u32),
+    Movk(Aarch64Reg, u16, u32),
+    Ldr(Aarch64Reg, i32),
+    Str(Aarch64Reg, i32),
+    AllocateStack(u32),
+    Ret,
+}
+
+#[derive(Debug, Clone)]
+pub enum Aarch64UnaryOperator {
+    Neg,
+    Mvn,
+}
+
+#[derive(Debug, Clone)]
+pub enum Aarch64Operand {
+    Imm(u32),
+    Register(Aarch64Reg),
+    Pseudo(Aarch64Identifier),
+    Stack(i32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Aarch64Reg {
+    W0,
+    W9,
+}
@@ -1,52 +1,155 @@
+use std::fmt;
+
+use crate::frame::FrameLayout;
+use crate::span::Span;
+use super::asm;
+
 pub mod ast_node_variants {
     pub use super::AsmProgram::*;
     pub use super::AsmFunctionDefinition::*;
     pub use super::AsmIdentifier::*;
     pub use super::AsmInstruction::*;
     pub use super::AsmUnaryOperator::*;
+    pub use super::AsmBinaryOperator::*;
     pub use super::AsmOperand::*;
     pub use super::AsmReg::*;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AsmProgram {
     Program(AsmFunctionDefinition),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AsmFunctionDefinition {
-    Function(AsmIdentifier, Vec<AsmInstruction>),
+    Function(AsmIdentifier, Vec<AsmInstruction>, FrameLayout, Span),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AsmIdentifier {
     Identifier(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AsmInstruction {
-    Mov(AsmOperand, AsmOperand),
-    Unary(AsmUnaryOperator, AsmOperand),
-    AllocateStack(u32),
-    Ret,
+    Mov(AsmOperand, AsmOperand, Span),
+    Unary(AsmUnaryOperator, AsmOperand, Span),
+    Binary(AsmBinaryOperator, AsmOperand, AsmOperand, Span),
+    AllocateStack(u32, Span),
+    Push(AsmReg, Span),
+    Pop(AsmReg, Span),
+    Ret(Span),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AsmUnaryOperator {
     Neg,
     Not,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub enum AsmBinaryOperator {
+    Xor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AsmOperand {
     Imm(u32),
     Register(AsmReg),
     Pseudo(AsmIdentifier),
-    Stack(u32),
+    Stack(i32),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum AsmReg {
     AX,
+    BX,
+    CX,
+    DX,
+    SI,
+    DI,
+    R8,
+    R9,
     R10,
+    R11,
+    R12,
+    R13,
+    R14,
+    R15,
+}
+
+// Pseudo-assembly in AT&T operand order, before or after pseudos are replaced: `mov $2, tmp0`, `neg -4(%rbp)`.
+
+impl fmt::Display for AsmProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let asm::Program(function_definition) = self;
+        write!(f, "{function_definition}")
+    }
+}
+
+impl fmt::Display for AsmFunctionDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let asm::Function(name, instructions, ..) = self;
+        writeln!(f, "{name}:")?;
+        for instruction in instructions {
+            writeln!(f, "    {instruction}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AsmIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let asm::Identifier(name) = self;
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for AsmInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            asm::Mov(src, dst, _) => write!(f, "mov {src}, {dst}"),
+            asm::Unary(operator, operand, _) => write!(f, "{operator} {operand}"),
+            asm::Binary(operator, src, dst, _) => write!(f, "{operator} {src}, {dst}"),
+            asm::AllocateStack(size, _) => write!(f, "allocate_stack {size}"),
+            asm::Push(reg, _) => write!(f, "push {reg}"),
+            asm::Pop(reg, _) => write!(f, "pop {reg}"),
+            asm::Ret(_) => f.write_str("ret"),
+        }
+    }
+}
+
+impl fmt::Display for AsmUnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            asm::Neg => f.write_str("neg"),
+            asm::Not => f.write_str("not"),
+        }
+    }
+}
+
+impl fmt::Display for AsmBinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            asm::Xor => f.write_str("xor"),
+        }
+    }
+}
+
+impl fmt::Display for AsmOperand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            asm::Imm(integer) => write!(f, "${integer}"),
+            asm::Register(reg) => write!(f, "{reg}"),
+            asm::Pseudo(identifier) => write!(f, "{identifier}"),
+            asm::Stack(offset) => write!(f, "{offset}(%rbp)"),
+        }
+    }
+}
+
+// Operands are sized by the instruction, so registers print by their family name.
+impl fmt::Display for AsmReg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", format!("{self:?}").to_lowercase())
+    }
 }
@@ -1,3 +1,8 @@
+use std::fmt;
+
+use crate::span::Span;
+use super::c;
+
 pub mod ast_node_variants {
     pub use super::CProgram::*;
     pub use super::CFunctionDefinition::*;
@@ -7,34 +12,89 @@ pub mod ast_node_variants {
     pub use super::CUnaryOperator::*;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CProgram {
     Program(CFunctionDefinition),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CFunctionDefinition {
-    Function(CIdentifier, CStatement),
+    Function(CIdentifier, CStatement, Span),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CIdentifier {
-    Identifier(String),
+    Identifier(String, Span),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CStatement {
-    Return(CExpression),
+    Return(CExpression, Span),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CExpression {
-    Constant(u32),
-    Unary(CUnaryOperator, Box<CExpression>),
+    Constant(u32, Span),
+    Unary(CUnaryOperator, Box<CExpression>, Span),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CUnaryOperator {
     Complement,
     Negate,
 }
+
+// Printed back as C source that parses to the same tree.
+
+impl fmt::Display for CProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c::Program(function_definition) = self;
+        write!(f, "{function_definition}")
+    }
+}
+
+impl fmt::Display for CFunctionDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c::Function(name, body, _) = self;
+        writeln!(f, "int {name}(void) {{")?;
+        writeln!(f, "    {body}")?;
+        writeln!(f, "}}")
+    }
+}
+
+impl fmt::Display for CIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c::Identifier(name, _) = self;
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for CStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            c::Return(expression, _) => write!(f, "return {expression};"),
+        }
+    }
+}
+
+impl fmt::Display for CExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            c::Constant(integer, _) => write!(f, "{integer}"),
+            // A nested operator is parenthesized, or `- -2` would print as the decrement `--2`.
+            c::Unary(operator, inner, _) => match **inner {
+                c::Unary(..) => write!(f, "{operator}({inner})"),
+                c::Constant(..) => write!(f, "{operator}{inner}"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for CUnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            c::Complement => f.write_str("~"),
+            c::Negate => f.write_str("-"),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::frame::FrameLayout;
+
+pub mod ast_node_variants {
+    pub use super::Riscv64Program::*;
+    pub use super::Riscv64FunctionDefinition::*;
+    pub use super::Riscv64Identifier::*;
+    pub use super::Riscv64Instruction::*;
+    pub use super::Riscv64UnaryOperator::*;
+    pub use super::Riscv64Operand::*;
+    pub use super::Riscv64Reg::*;
+}
+
+#[derive(Debug, Clone)]
+pub enum Riscv64Program {
+    Program(Riscv64FunctionDefinition),
+}
+
+#[derive(Debug, Clone)]
+pub enum Riscv64FunctionDefinition {
+    Function(Riscv64Identifier, Vec<Riscv64Instruction>, FrameLayout),
+}
+
+#[derive(Debug, Clone)]
+pub enum Riscv64Identifier {
+    Identifier(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Riscv64Instruction {
+    Mv(Riscv64Operand, Riscv64Operand),
+    Unary(Riscv64UnaryOperator, Riscv64Operand, Riscv64Operand),
+    Lui(Riscv64Reg, u32),
+    Addi(Riscv64Reg, Riscv64Reg, i32),
+    Addiw(Riscv64Reg, Riscv64Reg, i32),
+    Lw(Riscv64Reg, i32),
+    Sw(Riscv64Reg, i32),
+    AllocateStack(u32),
+    Ret,
+}
+
+#[derive(Debug, Clone)]
+pub enum Riscv64UnaryOperator {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone)]
+pub enum Riscv64Operand {
+    Imm(u32),
+    Register(Riscv64Reg),
+    Pseudo(Riscv64Identifier),
+    Stack(i32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Riscv64Reg {
+    Zero,
+    A0,
+    T0,
+}
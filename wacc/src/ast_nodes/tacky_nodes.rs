@@ -1,3 +1,8 @@
+use std::fmt;
+
+use crate::span::Span;
+use super::tacky;
+
 pub mod ast_node_variants {
     pub use super::TackyProgram::*;
     pub use super::TackyFunctionDefinition::*;
@@ -7,14 +12,14 @@ pub mod ast_node_variants {
     pub use super::TackyUnaryOperator::*;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TackyProgram {
     Program(TackyFunctionDefinition),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TackyFunctionDefinition {
-    Function(TackyIdentifier, Vec<TackyInstruction>)
+    Function(TackyIdentifier, Vec<TackyInstruction>, Span),
 }
 
 #[derive(Debug, Clone)]
@@ -22,10 +27,10 @@ pub enum TackyIdentifier {
     Identifier(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TackyInstruction {
-    Return(TackyOperand),
-    Unary(TackyUnaryOperator, TackyOperand, TackyOperand),
+    Return(TackyOperand, Span),
+    Unary(TackyUnaryOperator, TackyOperand, TackyOperand, Span),
 }
 
 #[derive(Debug, Clone)]
@@ -34,8 +39,62 @@ pub enum TackyOperand {
     Variable(TackyIdentifier),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TackyUnaryOperator {
     Complement,
     Negate,
 }
+
+// Three-address text, one instruction per line: `tmp1 = ~tmp0`.
+
+impl fmt::Display for TackyProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tacky::Program(function_definition) = self;
+        write!(f, "{function_definition}")
+    }
+}
+
+impl fmt::Display for TackyFunctionDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tacky::Function(name, instructions, _) = self;
+        writeln!(f, "function {name}:")?;
+        for instruction in instructions {
+            writeln!(f, "    {instruction}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TackyIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tacky::Identifier(name) = self;
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for TackyInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            tacky::Return(value, _) => write!(f, "return {value}"),
+            tacky::Unary(operator, src, dst, _) => write!(f, "{dst} = {operator}{src}"),
+        }
+    }
+}
+
+impl fmt::Display for TackyOperand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            tacky::Constant(integer) => write!(f, "{integer}"),
+            tacky::Variable(identifier) => write!(f, "{identifier}"),
+        }
+    }
+}
+
+impl fmt::Display for TackyUnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            tacky::Complement => f.write_str("~"),
+            tacky::Negate => f.write_str("-"),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::frame::FrameLayout;
+
+pub mod ast_node_variants {
+    pub use super::WasmModule::*;
+    pub use super::WasmFunction::*;
+    pub use super::WasmIdentifier::*;
+    pub use super::WasmInstruction::*;
+}
+
+#[derive(Debug, Clone)]
+pub enum WasmModule {
+    Module(WasmFunction),
+}
+
+#[derive(Debug, Clone)]
+pub enum WasmFunction {
+    Function(WasmIdentifier, Vec<WasmIdentifier>, Vec<WasmInstruction>, FrameLayout),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WasmIdentifier {
+    Identifier(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum WasmInstruction {
+    I32Const(i32),
+    I32Add,
+    I32Sub,
+    I32Xor,
+    I32Load(u32),
+    I32Store(u32),
+    LocalGet(WasmIdentifier),
+    LocalTee(WasmIdentifier),
+    GlobalGet(WasmIdentifier),
+    GlobalSet(WasmIdentifier),
+    Return,
+}
@@ -0,0 +1,72 @@
+//! # Cleanup
+//!
+//! Intermediate files live in a private directory under the system's temporary directory, never next to the inputs,
+//! so they can't clobber the user's files. `TempDir` removes it when dropped, which covers error returns and panics.
+//!
+//! Ctrl-C interrupts gcc along with `wacc`, as they share the terminal's process group. `wacc` itself only records
+//! the interrupt, so that the driver can stop at the next stage boundary and drop its `TempDir` before exiting.
+
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+pub struct TempDir {
+    path: PathBuf,
+    files: Cell<usize>,
+}
+
+impl TempDir {
+    pub fn new() -> Result<Self, String> {
+        static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+        loop {
+            let n = NEXT_DIR.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!("wacc-{}-{n}", std::process::id()));
+            // Creating the directory fails if it exists, so it's never shared with another process.
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self { path, files: Cell::new(0) }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("Failed to create temporary directory `{}`: {e}", path.display())),
+            }
+        }
+    }
+
+    /// A fresh path in the directory for an intermediate file of `source`.
+    pub fn file_for(&self, source: &Path, extension: &str) -> PathBuf {
+        let n = self.files.replace(self.files.get() + 1);
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        self.path.join(format!("{n}-{stem}.{extension}"))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.path).ok();
+    }
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" {
+    fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+}
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_: i32) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Makes Ctrl-C set the flag `interrupted` returns instead of killing `wacc`.
+pub fn catch_interrupts() {
+    #[cfg(unix)]
+    {
+        const SIGINT: i32 = 2;
+        // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+        unsafe { signal(SIGINT, on_interrupt) };
+    }
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
@@ -1,8 +1,10 @@
-use std::collections::HashMap;
 use crate::ast_nodes::*;
+use crate::frame::FrameLayout;
+use crate::regalloc::allocate_registers;
 
 pub fn gen_asm_program(tacky_program: TackyProgram) -> AsmProgram {
     let mut asm_program = gen_program(tacky_program);
+    allocate_registers(&mut asm_program);
     assign_pseudo_registers_to_stack(&mut asm_program);
     fix_invalid_mov_instructions(&mut asm_program);
     asm_program
@@ -14,21 +16,21 @@ fn gen_program(tacky_program: TackyProgram) -> AsmProgram {
 }
 
 fn gen_function_definition(tacky_function_definition: TackyFunctionDefinition) -> AsmFunctionDefinition {
-    let tacky::Function(tacky::Identifier(name), tacky_instructions) = tacky_function_definition;
+    let tacky::Function(tacky::Identifier(name), tacky_instructions, span) = tacky_function_definition;
     let mut asm_instructions = Vec::new();
     for instruction in tacky_instructions {
         match instruction {
-            tacky::Return(val) => {
-                asm_instructions.push(asm::Mov(gen_operand(val), asm::Register(asm::AX)));
-                asm_instructions.push(AsmInstruction::Ret);
+            tacky::Return(val, span) => {
+                asm_instructions.push(asm::Mov(gen_operand(val), asm::Register(asm::AX), span.clone()));
+                asm_instructions.push(AsmInstruction::Ret(span));
             },
-            TackyInstruction::Unary(operator, src, dst) => {
-                asm_instructions.push(asm::Mov(gen_operand(src), gen_operand(dst.clone())));
-                asm_instructions.push(asm::Unary(gen_unary_operator(operator), gen_operand(dst)));
+            TackyInstruction::Unary(operator, src, dst, span) => {
+                asm_instructions.push(asm::Mov(gen_operand(src), gen_operand(dst.clone()), span.clone()));
+                asm_instructions.push(asm::Unary(gen_unary_operator(operator), gen_operand(dst), span));
             },
         }
     }
-    asm::Function(asm::Identifier(name), asm_instructions)
+    asm::Function(asm::Identifier(name), asm_instructions, FrameLayout::default(), span)
 }
 
 fn gen_operand(tacky_value: TackyOperand) -> AsmOperand {
@@ -50,43 +52,48 @@ fn gen_unary_operator(tacky_operator: TackyUnaryOperator) -> AsmUnaryOperator {
     }
 }
 
-fn assign_pseudo_registers_to_stack(asm_program: &mut AsmProgram) {
-    let asm::Program(asm::Function(_, instructions)) = asm_program;
-    let mut stack_map = HashMap::new();
+/// Gives every pseudo register left by the register allocator a frame slot, and allocates the frame.
+pub(crate) fn assign_pseudo_registers_to_stack(asm_program: &mut AsmProgram) {
+    let asm::Program(asm::Function(_, instructions, frame_layout, span)) = asm_program;
     for instruction in instructions.iter_mut() {
         match instruction {
-            asm::Mov(src, dst) => {
-                check_and_replace_pseudo_register(src, &mut stack_map);
-                check_and_replace_pseudo_register(dst, &mut stack_map);
+            asm::Mov(src, dst, _) => {
+                check_and_replace_pseudo_register(src, frame_layout);
+                check_and_replace_pseudo_register(dst, frame_layout);
             },
-            asm::Unary(_, dst) => {
-                check_and_replace_pseudo_register(dst, &mut stack_map);
+            asm::Unary(_, dst, _) => {
+                check_and_replace_pseudo_register(dst, frame_layout);
+            },
+            asm::Binary(_, src, dst, _) => {
+                check_and_replace_pseudo_register(src, frame_layout);
+                check_and_replace_pseudo_register(dst, frame_layout);
             },
             _ => {},
         }
     }
-    instructions.insert(0, asm::AllocateStack(stack_map.len() as u32));
+    let pushed_bytes = instructions.iter().take_while(|instruction| matches!(instruction, asm::Push(..))).count() as u32 * 8;
+    frame_layout.finish(pushed_bytes);
+    instructions.insert(0, asm::AllocateStack(frame_layout.size(), span.clone()));
 }
 
-fn check_and_replace_pseudo_register(asm_operand: &mut AsmOperand, stack_map: &mut HashMap<String, u32>) {
+// Every pseudo holds an `int` for now.
+fn check_and_replace_pseudo_register(asm_operand: &mut AsmOperand, frame_layout: &mut FrameLayout) {
     if let asm::Pseudo(asm::Identifier(name)) = asm_operand {
-        if !stack_map.contains_key(name) {
-            stack_map.insert(name.clone(), stack_map.len() as u32);
-        }
-        *asm_operand = asm::Stack(*stack_map.get(name).unwrap());
+        *asm_operand = asm::Stack(frame_layout.allocate(name, 4, 4));
     }
 }
 
-fn fix_invalid_mov_instructions(asm_program: &mut AsmProgram) {
-    let asm::Program(asm::Function(_, instructions)) = asm_program;
+/// Splits `mov`s between two frame slots, which x86-64 can't encode, into two through `%r10`.
+pub(crate) fn fix_invalid_mov_instructions(asm_program: &mut AsmProgram) {
+    let asm::Program(asm::Function(_, instructions, ..)) = asm_program;
     let mut list = Vec::new();
     for (fix_pos, instruction) in instructions.iter().enumerate() {
-        if let &asm::Mov(asm::Stack(a_src), asm::Stack(a_dst)) = instruction {
-            list.push((fix_pos, a_src, a_dst));
+        if let asm::Mov(asm::Stack(a_src), asm::Stack(a_dst), span) = instruction {
+            list.push((fix_pos, *a_src, *a_dst, span.clone()));
         }
     }
-    for (i, (fix_pos, a_src, a_dst)) in list.iter().enumerate() {
-        instructions[fix_pos + i] = asm::Mov(asm::Stack(*a_src), asm::Register(asm::R10));
-        instructions.insert(fix_pos + i + 1, asm::Mov(asm::Register(asm::R10), asm::Stack(*a_dst)));
+    for (i, (fix_pos, a_src, a_dst, span)) in list.into_iter().enumerate() {
+        instructions[fix_pos + i] = asm::Mov(asm::Stack(a_src), asm::Register(asm::R10), span.clone());
+        instructions.insert(fix_pos + i + 1, asm::Mov(asm::Register(asm::R10), asm::Stack(a_dst), span));
     }
 }
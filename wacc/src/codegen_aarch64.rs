@@ -0,0 +1,129 @@
+use crate::ast_nodes::*;
+use crate::frame::FrameLayout;
+
+pub fn gen_aarch64_program(tacky_program: TackyProgram) -> Aarch64Program {
+    let mut aarch64_program = gen_program(tacky_program);
+    assign_pseudo_registers_to_stack(&mut aarch64_program);
+    legalize_instructions(&mut aarch64_program);
+    aarch64_program
+}
+
+fn gen_program(tacky_program: TackyProgram) -> Aarch64Program {
+    let tacky::Program(function_definition) = tacky_program;
+    aarch64::Program(gen_function_definition(function_definition))
+}
+
+fn gen_function_definition(tacky_function_definition: TackyFunctionDefinition) -> Aarch64FunctionDefinition {
+    let tacky::Function(tacky::Identifier(name), tacky_instructions, _) = tacky_function_definition;
+    let mut aarch64_instructions = Vec::new();
+    for instruction in tacky_instructions {
+        match instruction {
+            tacky::Return(val, _) => {
+                // AAPCS64 returns `int` in `w0`.
+                aarch64_instructions.push(aarch64::Mov(gen_operand(val), aarch64::Register(aarch64::W0)));
+                aarch64_instructions.push(Aarch64Instruction::Ret);
+            },
+            TackyInstruction::Unary(operator, src, dst, _) => {
+                aarch64_instructions.push(aarch64::Unary(gen_unary_operator(operator), gen_operand(src), gen_operand(dst)));
+            },
+        }
+    }
+    aarch64::Function(aarch64::Identifier(name), aarch64_instructions, FrameLayout::default())
+}
+
+fn gen_operand(tacky_value: TackyOperand) -> Aarch64Operand {
+    match tacky_value {
+        tacky::Constant(integer) => aarch64::Imm(integer),
+        tacky::Variable(tacky::Identifier(name)) => aarch64::Pseudo(aarch64::Identifier(name)),
+    }
+}
+
+fn gen_unary_operator(tacky_operator: TackyUnaryOperator) -> Aarch64UnaryOperator {
+    match tacky_operator {
+        tacky::Complement => aarch64::Mvn,
+        tacky::Negate => aarch64::Neg,
+    }
+}
+
+fn assign_pseudo_registers_to_stack(aarch64_program: &mut Aarch64Program) {
+    let aarch64::Program(aarch64::Function(_, instructions, frame_layout)) = aarch64_program;
+    for instruction in instructions.iter_mut() {
+        match instruction {
+            aarch64::Mov(src, dst) | aarch64::Unary(_, src, dst) => {
+                check_and_replace_pseudo_register(src, frame_layout);
+                check_and_replace_pseudo_register(dst, frame_layout);
+            },
+            _ => {},
+        }
+    }
+    // The stack pointer must stay 16-byte aligned at all times.
+    frame_layout.finish(0);
+    instructions.insert(0, aarch64::AllocateStack(frame_layout.size()));
+}
+
+fn check_and_replace_pseudo_register(aarch64_operand: &mut Aarch64Operand, frame_layout: &mut FrameLayout) {
+    if let aarch64::Pseudo(aarch64::Identifier(name)) = aarch64_operand {
+        *aarch64_operand = aarch64::Stack(frame_layout.allocate(name, 4, 4));
+    }
+}
+
+// AArch64 is a load/store architecture: arithmetic only works on registers, and immediates wider than 16 bits are
+// materialized with `movz`/`movk`. `w9` is the scratch register (caller-saved and not used for arguments).
+fn legalize_instructions(aarch64_program: &mut Aarch64Program) {
+    let aarch64::Program(aarch64::Function(_, instructions, _)) = aarch64_program;
+    let mut legalized = Vec::with_capacity(instructions.len());
+    for instruction in instructions.drain(..) {
+        match instruction {
+            aarch64::Mov(src, aarch64::Register(dst)) => {
+                load_operand(&mut legalized, src, dst);
+            },
+            aarch64::Mov(src, aarch64::Stack(offset)) => {
+                let reg = operand_in_register(&mut legalized, src);
+                legalized.push(aarch64::Str(reg, offset));
+            },
+            aarch64::Unary(operator, src, dst) => {
+                let src = operand_in_register(&mut legalized, src);
+                match dst {
+                    aarch64::Register(dst) => {
+                        legalized.push(aarch64::Unary(operator, aarch64::Register(src), aarch64::Register(dst)));
+                    },
+                    aarch64::Stack(offset) => {
+                        legalized.push(aarch64::Unary(operator, aarch64::Register(src), aarch64::Register(aarch64::W9)));
+                        legalized.push(aarch64::Str(aarch64::W9, offset));
+                    },
+                    _ => unreachable!("Unary destination should be a register or a stack slot"),
+                }
+            },
+            instruction => legalized.push(instruction),
+        }
+    }
+    *instructions = legalized;
+}
+
+fn operand_in_register(legalized: &mut Vec<Aarch64Instruction>, operand: Aarch64Operand) -> Aarch64Reg {
+    if let aarch64::Register(reg) = operand {
+        return reg;
+    }
+    load_operand(legalized, operand, aarch64::W9);
+    aarch64::W9
+}
+
+fn load_operand(legalized: &mut Vec<Aarch64Instruction>, operand: Aarch64Operand, dst: Aarch64Reg) {
+    match operand {
+        aarch64::Imm(integer) => {
+            let low = (integer & 0xffff) as u16;
+            let high = (integer >> 16) as u16;
+            if low == 0 && high != 0 {
+                legalized.push(aarch64::Movz(dst, high, 16));
+            } else {
+                legalized.push(aarch64::Movz(dst, low, 0));
+                if high != 0 {
+                    legalized.push(aarch64::Movk(dst, high, 16));
+                }
+            }
+        },
+        aarch64::Stack(offset) => legalized.push(aarch64::Ldr(dst, offset)),
+        aarch64::Register(src) => legalized.push(aarch64::Mov(aarch64::Register(src), aarch64::Register(dst))),
+        aarch64::Pseudo(_) => unreachable!("Pseudo registers should have been assigned to the stack"),
+    }
+}
@@ -0,0 +1,136 @@
+use crate::ast_nodes::*;
+use crate::frame::FrameLayout;
+
+pub fn gen_riscv64_program(tacky_program: TackyProgram) -> Riscv64Program {
+    let mut riscv64_program = gen_program(tacky_program);
+    assign_pseudo_registers_to_stack(&mut riscv64_program);
+    legalize_instructions(&mut riscv64_program);
+    riscv64_program
+}
+
+fn gen_program(tacky_program: TackyProgram) -> Riscv64Program {
+    let tacky::Program(function_definition) = tacky_program;
+    riscv64::Program(gen_function_definition(function_definition))
+}
+
+fn gen_function_definition(tacky_function_definition: TackyFunctionDefinition) -> Riscv64FunctionDefinition {
+    let tacky::Function(tacky::Identifier(name), tacky_instructions, _) = tacky_function_definition;
+    let mut riscv64_instructions = Vec::new();
+    for instruction in tacky_instructions {
+        match instruction {
+            tacky::Return(val, _) => {
+                // LP64D returns `int` sign-extended in `a0`.
+                riscv64_instructions.push(riscv64::Mv(gen_operand(val), riscv64::Register(riscv64::A0)));
+                riscv64_instructions.push(Riscv64Instruction::Ret);
+            },
+            TackyInstruction::Unary(operator, src, dst, _) => {
+                riscv64_instructions.push(riscv64::Unary(gen_unary_operator(operator), gen_operand(src), gen_operand(dst)));
+            },
+        }
+    }
+    riscv64::Function(riscv64::Identifier(name), riscv64_instructions, FrameLayout::default())
+}
+
+fn gen_operand(tacky_value: TackyOperand) -> Riscv64Operand {
+    match tacky_value {
+        tacky::Constant(integer) => riscv64::Imm(integer),
+        tacky::Variable(tacky::Identifier(name)) => riscv64::Pseudo(riscv64::Identifier(name)),
+    }
+}
+
+fn gen_unary_operator(tacky_operator: TackyUnaryOperator) -> Riscv64UnaryOperator {
+    match tacky_operator {
+        tacky::Complement => riscv64::Not,
+        tacky::Negate => riscv64::Neg,
+    }
+}
+
+// The frame pointer `s0` points at the caller's stack pointer, with the return address and the caller's `s0` saved
+// right below it, so those two slots are laid out first.
+fn assign_pseudo_registers_to_stack(riscv64_program: &mut Riscv64Program) {
+    let riscv64::Program(riscv64::Function(_, instructions, frame_layout)) = riscv64_program;
+    frame_layout.allocate("ra", 8, 8);
+    frame_layout.allocate("s0", 8, 8);
+    for instruction in instructions.iter_mut() {
+        match instruction {
+            riscv64::Mv(src, dst) | riscv64::Unary(_, src, dst) => {
+                check_and_replace_pseudo_register(src, frame_layout);
+                check_and_replace_pseudo_register(dst, frame_layout);
+            },
+            _ => {},
+        }
+    }
+    frame_layout.finish(0);
+    instructions.insert(0, riscv64::AllocateStack(frame_layout.size() - 16));
+}
+
+fn check_and_replace_pseudo_register(riscv64_operand: &mut Riscv64Operand, frame_layout: &mut FrameLayout) {
+    if let riscv64::Pseudo(riscv64::Identifier(name)) = riscv64_operand {
+        *riscv64_operand = riscv64::Stack(frame_layout.allocate(name, 4, 4));
+    }
+}
+
+// RISC-V is a load/store architecture: arithmetic only works on registers, and immediates wider than 12 bits are
+// materialized with `lui`/`addiw`. `t0` is the scratch register.
+fn legalize_instructions(riscv64_program: &mut Riscv64Program) {
+    let riscv64::Program(riscv64::Function(_, instructions, _)) = riscv64_program;
+    let mut legalized = Vec::with_capacity(instructions.len());
+    for instruction in instructions.drain(..) {
+        match instruction {
+            riscv64::Mv(src, riscv64::Register(dst)) => {
+                load_operand(&mut legalized, src, dst);
+            },
+            riscv64::Mv(src, riscv64::Stack(offset)) => {
+                let reg = operand_in_register(&mut legalized, src);
+                legalized.push(riscv64::Sw(reg, offset));
+            },
+            riscv64::Unary(operator, src, dst) => {
+                let src = operand_in_register(&mut legalized, src);
+                match dst {
+                    riscv64::Register(dst) => {
+                        legalized.push(riscv64::Unary(operator, riscv64::Register(src), riscv64::Register(dst)));
+                    },
+                    riscv64::Stack(offset) => {
+                        legalized.push(riscv64::Unary(operator, riscv64::Register(src), riscv64::Register(riscv64::T0)));
+                        legalized.push(riscv64::Sw(riscv64::T0, offset));
+                    },
+                    _ => unreachable!("Unary destination should be a register or a stack slot"),
+                }
+            },
+            instruction => legalized.push(instruction),
+        }
+    }
+    *instructions = legalized;
+}
+
+fn operand_in_register(legalized: &mut Vec<Riscv64Instruction>, operand: Riscv64Operand) -> Riscv64Reg {
+    if let riscv64::Register(reg) = operand {
+        return reg;
+    }
+    load_operand(legalized, operand, riscv64::T0);
+    riscv64::T0
+}
+
+fn load_operand(legalized: &mut Vec<Riscv64Instruction>, operand: Riscv64Operand, dst: Riscv64Reg) {
+    match operand {
+        riscv64::Imm(integer) => load_immediate(legalized, integer as i32, dst),
+        riscv64::Stack(offset) => legalized.push(riscv64::Lw(dst, offset)),
+        riscv64::Register(src) => legalized.push(riscv64::Mv(riscv64::Register(src), riscv64::Register(dst))),
+        riscv64::Pseudo(_) => unreachable!("Pseudo registers should have been assigned to the stack"),
+    }
+}
+
+// `lui` loads the upper 20 bits and `addiw` adds the sign-extended lower 12 bits, so the upper part is rounded up
+// whenever bit 11 is set.
+fn load_immediate(legalized: &mut Vec<Riscv64Instruction>, integer: i32, dst: Riscv64Reg) {
+    if (-2048..2048).contains(&integer) {
+        legalized.push(riscv64::Addi(dst, riscv64::Zero, integer));
+        return;
+    }
+    let upper = ((integer as i64 + 0x800) >> 12) as u32 & 0xfffff;
+    let lower = integer.wrapping_sub((upper << 12) as i32);
+    legalized.push(riscv64::Lui(dst, upper));
+    if lower != 0 {
+        legalized.push(riscv64::Addiw(dst, dst, lower));
+    }
+}
@@ -0,0 +1,108 @@
+//! # WebAssembly code generation
+//!
+//! Structured control flow would need a relooper-style pass to rebuild `block`/`loop`/`if` nesting from labels and
+//! jumps; a TACKY function body is a single basic block, which maps directly onto the function body.
+//!
+//! Like clang at `-O0`, every TACKY variable lives in a frame on a shadow stack in linear memory rather than in a wasm
+//! local, so that its address is an ordinary `i32`:
+//! - `$__stack_pointer` is the top of the shadow stack, which starts at the end of the first memory page and grows
+//!   down;
+//! - The prologue moves it down by the frame size and keeps the new value in the `$fp` local, and the epilogue before
+//!   each `return` moves it back up;
+//! - Slots are laid out by `FrameLayout` like the native frames, and are addressed as constant offsets from `$fp`.
+
+use crate::ast_nodes::*;
+use crate::frame::FrameLayout;
+
+pub const STACK_POINTER: &str = "__stack_pointer";
+const FRAME_POINTER: &str = "fp";
+
+pub fn gen_wasm_module(tacky_program: TackyProgram) -> WasmModule {
+    let tacky::Program(function_definition) = tacky_program;
+    wasm::Module(gen_function(function_definition))
+}
+
+fn gen_function(tacky_function_definition: TackyFunctionDefinition) -> WasmFunction {
+    let tacky::Function(tacky::Identifier(name), tacky_instructions, _) = tacky_function_definition;
+    let frame_layout = lay_out_frame(&tacky_instructions);
+
+    let mut wasm_instructions = Vec::new();
+    if frame_layout.size() > 0 {
+        wasm_instructions.extend([
+            wasm::GlobalGet(wasm::Identifier(STACK_POINTER.into())),
+            wasm::I32Const(frame_layout.size() as i32),
+            wasm::I32Sub,
+            wasm::LocalTee(wasm::Identifier(FRAME_POINTER.into())),
+            wasm::GlobalSet(wasm::Identifier(STACK_POINTER.into())),
+        ]);
+    }
+    for instruction in tacky_instructions {
+        match instruction {
+            tacky::Return(val, _) => {
+                gen_operand(&mut wasm_instructions, &frame_layout, val);
+                if frame_layout.size() > 0 {
+                    wasm_instructions.extend([
+                        wasm::LocalGet(wasm::Identifier(FRAME_POINTER.into())),
+                        wasm::I32Const(frame_layout.size() as i32),
+                        wasm::I32Add,
+                        wasm::GlobalSet(wasm::Identifier(STACK_POINTER.into())),
+                    ]);
+                }
+                wasm_instructions.push(wasm::Return);
+            },
+            TackyInstruction::Unary(operator, src, dst, _) => {
+                let tacky::Variable(tacky::Identifier(dst)) = dst else {
+                    unreachable!("Unary destination should be a variable");
+                };
+                // The address goes below the value for `i32.store`.
+                wasm_instructions.push(wasm::LocalGet(wasm::Identifier(FRAME_POINTER.into())));
+                match operator {
+                    tacky::Negate => {
+                        wasm_instructions.push(wasm::I32Const(0));
+                        gen_operand(&mut wasm_instructions, &frame_layout, src);
+                        wasm_instructions.push(wasm::I32Sub);
+                    },
+                    tacky::Complement => {
+                        gen_operand(&mut wasm_instructions, &frame_layout, src);
+                        wasm_instructions.push(wasm::I32Const(-1));
+                        wasm_instructions.push(wasm::I32Xor);
+                    },
+                }
+                wasm_instructions.push(wasm::I32Store(slot_offset(&frame_layout, &dst)));
+            },
+        }
+    }
+
+    let locals = if frame_layout.size() > 0 { vec![wasm::Identifier(FRAME_POINTER.into())] } else { Vec::new() };
+    wasm::Function(wasm::Identifier(name), locals, wasm_instructions, frame_layout)
+}
+
+// Every variable is written before it's read, so the destinations are all the slots.
+fn lay_out_frame(tacky_instructions: &[TackyInstruction]) -> FrameLayout {
+    let mut frame_layout = FrameLayout::default();
+    for instruction in tacky_instructions {
+        if let TackyInstruction::Unary(_, _, tacky::Variable(tacky::Identifier(dst)), _) = instruction {
+            frame_layout.allocate(dst, 4, 4);
+        }
+    }
+    // The shadow stack pointer stays 16-byte aligned, as in the C ABI for wasm32.
+    frame_layout.finish(0);
+    frame_layout
+}
+
+// Slot offsets are negative from the top of the frame, while memory operands take unsigned offsets from `$fp` at its
+// bottom.
+fn slot_offset(frame_layout: &FrameLayout, name: &str) -> u32 {
+    let offset = frame_layout.offset_of(name).expect("That every variable should have a slot");
+    (frame_layout.size() as i32 + offset) as u32
+}
+
+fn gen_operand(wasm_instructions: &mut Vec<WasmInstruction>, frame_layout: &FrameLayout, tacky_value: TackyOperand) {
+    match tacky_value {
+        tacky::Constant(integer) => wasm_instructions.push(wasm::I32Const(integer as i32)),
+        tacky::Variable(tacky::Identifier(name)) => {
+            wasm_instructions.push(wasm::LocalGet(wasm::Identifier(FRAME_POINTER.into())));
+            wasm_instructions.push(wasm::I32Load(slot_offset(frame_layout, &name)));
+        },
+    }
+}
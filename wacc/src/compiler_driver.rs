@@ -1,32 +1,84 @@
-use std::fs::File;
-use std::io::{Read, Write};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::lexer::{Lexer, Tokens};
+use crate::diagnostics::Diagnostic;
+use crate::lexer::Tokens;
 use crate::parser::Parser;
 use crate::ast_nodes::{AsmProgram, CProgram, TackyProgram};
 use crate::tackygen::gen_tacky_program;
-use crate::codegen::gen_asm_program;
-use crate::emit::emit_asm_program;
+use crate::tacky_parser::parse_tacky_program;
+use crate::optimizer::optimize_tacky_program;
+use crate::emit_llvm::emit_llvm_module;
+use crate::interpreter::interpret_tacky_program;
+use crate::simulator::simulate_asm_program;
+use crate::pipeline::{self, Options, Target, TargetProgram};
+use crate::emit::ObjectFormat;
+use crate::encode::encode_asm_program;
+use crate::elf::{read_object_file, write_object_file};
+use crate::linker::link_executable;
+use crate::cleanup::{catch_interrupts, interrupted, TempDir};
 
 use CompilerDriverOption::*;
 
 #[derive(Debug, Default, PartialEq, PartialOrd)]
 pub enum CompilerDriverOption {
     EmitReferenceAssembly = 0,
-    Lex = 1,
-    Parse = 2,
-    Tacky = 3,
-    Codegen = 4,
-    EmitAssembly = 5,
+    Preprocess = 1,
+    Lex = 2,
+    Parse = 3,
+    Tacky = 4,
+    Codegen = 5,
+    EmitAssembly = 6,
+    Assemble = 7,
     #[default]
-    All = 6,
+    All = 8,
+}
+
+/// An intermediate representation that `--dump-<name>` prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dump {
+    Tokens,
+    Ast,
+    Tacky,
+    Asm,
+}
+
+impl TryFrom<&str> for Dump {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "tokens" => Ok(Self::Tokens),
+            "ast" => Ok(Self::Ast),
+            "tacky" => Ok(Self::Tacky),
+            "asm" => Ok(Self::Asm),
+            _ => Err(format!("Unknown dump `--dump-{value}`, expected `tokens`, `ast`, `tacky` or `asm`")),
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct CompilerDriver {
     option: CompilerDriverOption,
-    filename: String,
+    inputs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    linker_options: Vec<String>,
+    options: Options,
+    warnings: Vec<Diagnostic>,
+    emit_llvm: bool,
+    interpret: bool,
+    simulate: bool,
+    from_tacky: bool,
+    external_assembler: bool,
+    external_linker: bool,
+    verbose: bool,
+    dumps: HashMap<Dump, Option<PathBuf>>,
+    temp_dir: Option<TempDir>,
+    exit_status: i32,
 }
 
 impl CompilerDriver {
@@ -34,139 +86,434 @@ impl CompilerDriver {
         self.option = option;
     }
 
-    pub fn set_filename(&mut self, filename: &str) {
-        self.filename = filename.into();
+    pub fn get_options_mut(&mut self) -> &mut Options {
+        &mut self.options
+    }
+
+    /// The warnings reported so far, unless `-Werror` turned them into errors.
+    pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn set_emit_llvm(&mut self) {
+        self.emit_llvm = true;
+    }
+
+    pub fn set_interpret(&mut self) {
+        self.interpret = true;
+    }
+
+    pub fn set_simulate(&mut self) {
+        self.simulate = true;
+    }
+
+    /// Reads `.tacky` inputs instead of `.c` ones, skipping the front end.
+    pub fn set_from_tacky(&mut self) {
+        self.from_tacky = true;
+    }
+
+    /// Assembles with gcc even where `wacc` can write the object file itself.
+    pub fn set_external_assembler(&mut self) {
+        self.external_assembler = true;
+    }
+
+    // Only x86-64 ELF objects can be written without an assembler, and not yet with debug info.
+    fn integrated_assembler(&self) -> bool {
+        !self.external_assembler && self.options.target() == Target::X86_64
+            && self.options.emit_options().object_format() == ObjectFormat::Elf
+            && !self.options.emit_options().debug_info()
+    }
+
+    /// Links with gcc even where the built-in linker could.
+    pub fn set_external_linker(&mut self) {
+        self.external_linker = true;
+    }
+
+    // The built-in linker only knows the objects of the integrated assembler, and links no libraries.
+    fn builtin_linker(&self) -> bool {
+        !self.external_linker && self.linker_options.is_empty() && self.integrated_assembler()
+    }
+
+    /// Prints the commands run and what the interpreted or simulated program returned to stderr.
+    pub fn set_verbose(&mut self) {
+        self.verbose = true;
+    }
+
+    /// Dumps `dump` to stdout, or to the file at `path`.
+    pub fn add_dump(&mut self, dump: Dump, path: Option<&str>) {
+        self.dumps.insert(dump, path.map(PathBuf::from));
+    }
+
+    // Dumps of every input go to the same file, so it's truncated once in `run` and appended to here.
+    fn dump(&self, dump: Dump, contents: impl FnOnce() -> String) -> Result<(), String> {
+        match self.dumps.get(&dump) {
+            None => Ok(()),
+            Some(None) => {
+                print!("{}", contents());
+                Ok(())
+            },
+            Some(Some(path)) => OpenOptions::new().append(true).open(path)
+                .and_then(|mut file| file.write_all(contents().as_bytes()))
+                .map_err(|e| format!("Failed to write dump to `{}`: {e}", path.display())),
+        }
+    }
+
+    /// The exit status `wacc` should report: what the interpreted or simulated program returned, otherwise 0.
+    pub fn exit_status(&self) -> i32 {
+        self.exit_status
+    }
+
+    /// Adds a `.c` file to compile, a `.s` file to assemble or a `.o` file to link.
+    pub fn add_input(&mut self, path: &str) {
+        self.inputs.push(path.into());
     }
 
-    fn filename_preprocessed(&self) -> String {
-        format!("{}.i", &self.filename[..self.filename.len()-2])
+    pub fn set_output(&mut self, path: &str) {
+        self.output = Some(path.into());
     }
 
-    fn filename_assembly(&self) -> String {
-        format!("{}.s", &self.filename[..self.filename.len()-2])
+    /// Adds a `-l` or `-L` option, passed to the linker in command-line order.
+    pub fn add_linker_option(&mut self, option: String) {
+        self.linker_options.push(option);
     }
 
-    fn filename_output(&self) -> String {
-        format!("{}", &self.filename[..self.filename.len()-2])
+    // Without `-o`, outputs go next to their source, and the executable is named after the first input.
+    fn output_path(&self, source: &Path, extension: &str) -> PathBuf {
+        self.output.clone().unwrap_or(source.with_extension(extension))
+    }
+
+    // WebAssembly has no assembler to hand the module to, so the `.wat` file is the final output.
+    fn assembly_is_output(&self) -> bool {
+        self.option == EmitAssembly || self.options.target().gcc().is_none()
+    }
+
+    fn filename_assembly(&self, source: &Path) -> PathBuf {
+        let extension = self.options.target().assembly_extension();
+        match &self.temp_dir {
+            Some(temp_dir) if !self.assembly_is_output() => temp_dir.file_for(source, extension),
+            _ => self.output_path(source, extension),
+        }
     }
 
     fn check_config(&self) -> Result<(), String> {
-        println!("Option: {:?}", self.option);
-        println!("Filename: `{}`", self.filename);
-        if self.filename.is_empty() {
-            return Err("No input file".into());
+        if self.inputs.is_empty() {
+            return Err("No input files".into());
+        }
+        let source_extension = if self.from_tacky { "tacky" } else { "c" };
+        for input in &self.inputs {
+            match input.extension().and_then(OsStr::to_str) {
+                Some("s" | "o") => (),
+                Some(extension) if extension == source_extension => (),
+                _ => return Err(format!("Unrecognized input file `{}`: expected a `.{source_extension}`, `.s` or `.o` file", input.display())),
+            }
         }
-        if !self.filename.ends_with(".c") {
-            return Err(format!("Filename `{}` should end with \".c\"", self.filename));
+        if self.from_tacky && self.option < Tacky {
+            return Err("`--from-tacky` starts from TACKY, so it can't stop at an earlier stage".into());
         }
+        let one_output_per_input = self.option < All || self.options.target().gcc().is_none() || self.emit_llvm;
+        if self.output.is_some() && one_output_per_input && self.inputs.len() > 1 {
+            return Err("Cannot specify `-o` with `-c`, `-S`, `-E` or `--emit-llvm` with multiple files".into());
+        }
+        if (self.interpret || self.simulate) && self.inputs.len() > 1 {
+            return Err("`--interpret` and `--simulate` run a single source file".into());
+        }
+        self.options.validate()
+    }
+
+    fn emit_reference_assembly(&self, source: &Path) -> Result<(), String> {
+        let assembly = self.output_path(source, "s");
+        let options: [&OsStr; 7] = ["-S".as_ref(), "-O".as_ref(), "-fno-asynchronous-unwind-tables".as_ref(), "-fcf-protection=none".as_ref(),
+            source.as_ref(), "-o".as_ref(), assembly.as_ref()];
+        toolchain("gcc", &options, self.verbose)
+    }
+
+    fn preprocess(&self, source: &Path) -> Result<String, String> {
+        self.options.preprocessor().preprocess(&source.to_string_lossy())
+    }
+
+    fn emit_preprocessed(&self, source: &Path, preprocessed: &str) -> Result<(), String> {
+        let path = self.output_path(source, "i");
+        File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {e}", path.display()))?
+            .write_all(preprocessed.as_bytes())
+            .map_err(|e| format!("Failed to write preprocessed file: {e}"))
+    }
+
+    fn parse(&self, tokens: Tokens) -> Result<CProgram, Vec<Diagnostic>> {
+        Parser::from(tokens).parse()
+    }
+
+    fn check(&mut self, c_program: &CProgram) -> Result<(), Vec<Diagnostic>> {
+        self.warnings.extend(pipeline::check(c_program, self.options.warning_options())?);
         Ok(())
     }
 
-    fn emit_reference_assembly(&self) -> Result<(), String> {
-        println!("--- Stage: EMIT REFERENCE ASSEMBLY ---");
-        gcc(&["-S", "-O", "-fno-asynchronous-unwind-tables", "-fcf-protection=none", &self.filename, "-o", &self.filename_assembly()])
+    fn tacky(&self, c_program: CProgram) -> TackyProgram {
+        gen_tacky_program(c_program)
+    }
+
+    fn optimize(&self, mut tacky_program: TackyProgram) -> TackyProgram {
+        optimize_tacky_program(&mut tacky_program, self.options.optimizations());
+        tacky_program
+    }
+
+    fn codegen(&self, tacky_program: TackyProgram) -> TargetProgram {
+        pipeline::codegen(tacky_program, &self.options)
+    }
+
+    fn emit_assembly(&self, source: &Path, target_program: TargetProgram) -> Result<PathBuf, String> {
+        let asm_code = pipeline::emit(target_program, &self.options);
+
+        let path = self.filename_assembly(source);
+        let mut asm_file = File::create(&path)
+            .map_err(|e| format!("Failed to create file `{}`: {e}", path.display()))?;
+        writeln!(asm_file, "{asm_code}")
+            .map_err(|e| format!("Failed to write assembly code to `{}`: {e}", path.display()))?;
+        Ok(path)
     }
 
-    fn preprocess(&self) -> Result<(), String> {
-        println!("--- Stage: PREPROCESS ---");
-        gcc(&["-E", "-P", &self.filename, "-o", &self.filename_preprocessed()])
+    fn write_object(&self, source: &Path, asm_program: &AsmProgram) -> Result<PathBuf, String> {
+        let object = encode_asm_program(asm_program, self.options.emit_options());
+        let path = match &self.temp_dir {
+            Some(temp_dir) if self.option == All => temp_dir.file_for(source, "o"),
+            _ => self.output_path(source, "o"),
+        };
+        fs::write(&path, write_object_file(&object))
+            .map_err(|e| format!("Failed to write object file `{}`: {e}", path.display()))?;
+        Ok(path)
     }
 
-    fn lex(&self) -> Result<Lexer, String> {
-        println!("--- Stage: LEX ---");
+    fn emit_llvm_ir(&self, source: &Path, tacky_program: TackyProgram) -> Result<(), String> {
+        let llvm_code = emit_llvm_module(tacky_program, &source.to_string_lossy());
 
-        let mut lexer = Lexer::default();
-        File::open(self.filename_preprocessed())
-            .map_err(|e| format!("Failed to open {}: {e}", self.filename_preprocessed()))?
-            .read_to_string(lexer.get_src_mut())
-            .map_err(|e| format!("Failed to read preprocessed file: {e}"))?;
-        Command::new("rm").arg(&self.filename_preprocessed()).status()
-            .map_err(|e| format!("Failed to delete `{}`: {e}", self.filename_preprocessed()))?;
+        let path = self.output_path(source, "ll");
+        let mut llvm_file = File::create(&path)
+            .map_err(|e| format!("Failed to create file `{}`: {e}", path.display()))?;
+        write!(llvm_file, "{llvm_code}")
+            .map_err(|e| format!("Failed to write LLVM IR to `{}`: {e}", path.display()))
+    }
 
-        for token in lexer.tokens() {
-            println!("token: {}", token?);
+    fn interpret(&mut self, tacky_program: TackyProgram) -> Result<(), String> {
+        self.exit_status = interpret_tacky_program(&tacky_program)?;
+        if self.verbose {
+            eprintln!("Program returned {}", self.exit_status);
         }
-        Ok(lexer)
+        Ok(())
     }
 
-    fn parse(&self, tokens: Tokens) -> Result<CProgram, String> {
-        println!("--- Stage: PARSE ---");
-        let c_program = Parser::from(tokens).parse()?;
-        println!("Abstract syntax tree:\n{c_program:#?}");
-        Ok(c_program)
+    fn simulate(&mut self, target_program: &TargetProgram) -> Result<(), String> {
+        let TargetProgram::X86_64(asm_program) = target_program else {
+            return Err(format!("Only x86-64 programs can be simulated, not {:?}", self.options.target()));
+        };
+        let simulation = simulate_asm_program(asm_program)?;
+        if self.verbose {
+            eprintln!("Program returned {} with flags {}", simulation.result, simulation.flags);
+        }
+        self.exit_status = simulation.result;
+        Ok(())
     }
 
-    fn tacky(&self, c_program: CProgram) -> TackyProgram {
-        println!("--- Stage: PARSE ---");
-        let tacky = gen_tacky_program(c_program);
-        println!("Tacky:\n{tacky:#?}");
-        tacky
+    // Objects are named after the inputs, not the intermediate assembly files.
+    fn assemble(&self, gcc: &str, assembly: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+        for (input, path) in assembly {
+            let object = self.output_path(input, "o");
+            toolchain(gcc, &["-c".as_ref(), path.as_os_str(), "-o".as_ref(), object.as_os_str()], self.verbose)?;
+        }
+        Ok(())
     }
 
-    fn codegen(&self, tacky_program: TackyProgram) -> AsmProgram {
-        println!("--- Stage: CODEGEN ---");
-        let asm_program = gen_asm_program(tacky_program);
-        println!("Generated assembly program:\n{asm_program:#?}");
-        asm_program
+    fn link(&self, gcc: &str, linker_inputs: &[PathBuf]) -> Result<(), String> {
+        let executable = self.output_path(&self.inputs[0], "");
+        let mut options: Vec<&OsStr> = linker_inputs.iter().map(|path| path.as_os_str()).collect();
+        options.extend(self.linker_options.iter().map(OsStr::new));
+        options.extend(["-o".as_ref(), executable.as_os_str()]);
+        toolchain(gcc, &options, self.verbose)
     }
 
-    fn emit_assembly(&self, asm_program: AsmProgram) -> Result<(), String> {
-        println!("--- Stage: EMIT ASSEMBLY ---");
-        let asm_code = emit_asm_program(asm_program);
-        println!("Emit assembly code:\n{asm_code}");
+    fn link_builtin(&self, objects: &[PathBuf]) -> Result<(), String> {
+        let objects = objects.iter()
+            .map(|path| fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| read_object_file(&bytes))
+                .map_err(|e| format!("`{}`: {e}", path.display())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let executable_file = link_executable(&objects, &self.options.emit_options().symbol("main"))?;
+
+        let executable = self.output_path(&self.inputs[0], "");
+        fs::write(&executable, executable_file)
+            .map_err(|e| format!("Failed to write executable `{}`: {e}", executable.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&executable, fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Failed to make `{}` executable: {e}", executable.display()))?;
+        }
+        Ok(())
+    }
 
-        let mut asm_file = File::create(self.filename_assembly())
-            .map_err(|e| format!("Failed to create file `{}`: {e}", self.filename_assembly()))?;
-        writeln!(asm_file, "{asm_code}")
-            .map_err(|e| format!("Failed to write assembly code to `{}`: {e}", self.filename_assembly()))
+    pub fn run(&mut self) -> Result<(), Vec<Diagnostic>> {
+        catch_interrupts();
+        let result = self.run_stages();
+        // Removes the intermediate files on every path out of the stages.
+        self.temp_dir = None;
+        if interrupted() {
+            return Err(vec![Diagnostic::error("interrupted")]);
+        }
+        result
+    }
+
+    fn run_stages(&mut self) -> Result<(), Vec<Diagnostic>> {
+        self.check_config().map_err(|e| vec![Diagnostic::from(e)])?;
+        for path in self.dumps.values().flatten() {
+            File::create(path)
+                .map_err(|e| vec![Diagnostic::from(format!("Failed to create dump file `{}`: {e}", path.display()))])?;
+        }
+        if self.option >= Assemble && self.options.target().gcc().is_some() {
+            self.temp_dir = Some(TempDir::new().map_err(|e| vec![Diagnostic::from(e)])?);
+        }
+
+        // `.s` inputs are assembled and `.o` inputs linked as they are.
+        let mut assembly = Vec::new();
+        let mut objects = Vec::new();
+        for input in self.inputs.clone() {
+            if interrupted() {
+                return Ok(());
+            }
+            match input.extension().and_then(OsStr::to_str) {
+                Some("c" | "tacky") => match self.compile(&input)? {
+                    Some(path) if path.extension().is_some_and(|extension| extension == "o") => objects.push(path),
+                    Some(path) => assembly.push((input, path)),
+                    None => (),
+                },
+                Some("s") => assembly.push((input.clone(), input)),
+                _ => objects.push(input),
+            }
+        }
+
+        match self.options.target().gcc() {
+            Some(gcc) if self.option == Assemble => self.assemble(gcc, &assembly)
+                .map_err(stage_failed("Assemble")),
+            Some(gcc) if self.option == All && !self.interpret && !self.simulate && !self.emit_llvm => {
+                // Programs that need libc, or anything else the built-in linker lacks, are linked by gcc instead.
+                if assembly.is_empty() && self.builtin_linker() {
+                    match self.link_builtin(&objects) {
+                        Ok(()) => return Ok(()),
+                        Err(e) if self.verbose => eprintln!("Linking with {gcc}: {e}"),
+                        Err(_) => (),
+                    }
+                }
+                let assembly = assembly.into_iter().map(|(_, path)| path);
+                self.link(gcc, &assembly.chain(objects).collect::<Vec<_>>())
+                    .map_err(stage_failed("Assemble and link"))
+            },
+            _ => Ok(()),
+        }
     }
 
-    fn assemble_and_link(&self) -> Result<(), String> {
-        println!("--- Stage: ASSEMBLE & LINK ---");
-        gcc(&[&self.filename_assembly(), "-o", &self.filename_output()])?;
-        Command::new("rm").arg(&self.filename_assembly()).status().map(|_| {})
-            .map_err(|e| format!("Failed to delete `{}`: {e}", self.filename_assembly()))
+    // Runs the stages up to `option` on a source file, and returns the assembly file when it's left for gcc.
+    fn compile(&mut self, source: &Path) -> Result<Option<PathBuf>, Vec<Diagnostic>> {
+        let tacky_program = if self.from_tacky {
+            self.read_tacky(source)?
+        } else {
+            match self.front_end(source)? {
+                Some(tacky_program) => tacky_program,
+                None => return Ok(None),
+            }
+        };
+        self.back_end(source, tacky_program)
     }
 
-    pub fn run(&mut self) -> Result<(), String> {
-        self.check_config()?;
+    fn read_tacky(&self, source: &Path) -> Result<TackyProgram, Vec<Diagnostic>> {
+        let src = fs::read_to_string(source)
+            .map_err(|e| vec![Diagnostic::error(format!("Failed to read `{}`: {e}", source.display()))])?;
+        parse_tacky_program(&src, &source.to_string_lossy())
+    }
 
+    // Runs the stages from preprocessing to TACKY generation, unless `option` stops earlier.
+    fn front_end(&mut self, source: &Path) -> Result<Option<TackyProgram>, Vec<Diagnostic>> {
         if self.option == EmitReferenceAssembly {
-            self.emit_reference_assembly()
-                .map_err(|e| format!("`Emit Referenct Assembly` stage failed: {e}"))?;
+            self.emit_reference_assembly(source)
+                .map_err(stage_failed("Emit Referenct Assembly"))?;
         }
 
-        if self.option < Lex { return Ok(()) }
-        self.preprocess()
-            .map_err(|e| format!("`Preprocess` stage failed: {e}"))?;
-        let lexer = self.lex()
-            .map_err(|e| format!("`Lex` stage failed: {e}"))?;
+        if self.option < Preprocess { return Ok(None) }
+        let preprocessed = self.preprocess(source)
+            .map_err(stage_failed("Preprocess"))?;
+        if self.option < Lex {
+            self.emit_preprocessed(source, &preprocessed)
+                .map_err(stage_failed("Preprocess"))?;
+            return Ok(None);
+        }
+        let lexer = pipeline::lex(preprocessed)?;
+        self.dump(Dump::Tokens, || lexer.tokens().flatten().map(|(token, span)| format!("{span}: {token}\n")).collect())
+            .map_err(stage_failed("Lex"))?;
+
+        if self.option < Parse { return Ok(None) }
+        let c_program = self.parse(lexer.tokens())?;
+        self.dump(Dump::Ast, || c_program.to_string())
+            .map_err(stage_failed("Parse"))?;
+        self.check(&c_program)?;
+
+        if self.option < Tacky { return Ok(None) }
+        Ok(Some(self.tacky(c_program)))
+    }
 
-        if self.option < Parse { return Ok(()) }
-        let c_program = self.parse(lexer.tokens())
-            .map_err(|e| format!("`Parse` stage failed: {e}"))?;
+    fn back_end(&mut self, source: &Path, mut tacky_program: TackyProgram) -> Result<Option<PathBuf>, Vec<Diagnostic>> {
+        if self.options.optimizations().any_tacky() {
+            tacky_program = self.optimize(tacky_program);
+        }
+        self.dump(Dump::Tacky, || tacky_program.to_string())
+            .map_err(stage_failed("Tacky"))?;
 
-        if self.option < Tacky { return Ok(()) }
-        let tacky_program = self.tacky(c_program);
+        if self.interpret {
+            self.interpret(tacky_program)
+                .map_err(stage_failed("Interpret"))?;
+            return Ok(None);
+        }
 
-        if self.option < Codegen { return Ok(()) }
-        let asm_program = self.codegen(tacky_program);
+        if self.emit_llvm {
+            self.emit_llvm_ir(source, tacky_program)
+                .map_err(stage_failed("Emit LLVM IR"))?;
+            return Ok(None);
+        }
 
-        if self.option < EmitAssembly { return Ok(()) }
-        self.emit_assembly(asm_program)
-            .map_err(|e| format!("`Emit assembly` stage failed: {e}"))?;
+        if self.option < Codegen { return Ok(None) }
+        let target_program = self.codegen(tacky_program);
+        self.dump(Dump::Asm, || target_program.dump())
+            .map_err(stage_failed("Codegen"))?;
+        if self.simulate {
+            self.simulate(&target_program)
+                .map_err(stage_failed("Simulate"))?;
+            return Ok(None);
+        }
 
-        if self.option < All { return Ok(()) }
-        self.assemble_and_link()
-            .map_err(|e| format!("`Assemble and link` stage failed: {e}"))
+        if self.option < EmitAssembly { return Ok(None) }
+        if let (true, TargetProgram::X86_64(asm_program)) = (self.option >= Assemble && self.integrated_assembler(), &target_program) {
+            let path = self.write_object(source, asm_program)
+                .map_err(stage_failed("Assemble"))?;
+            return Ok(Some(path));
+        }
+        let path = self.emit_assembly(source, target_program)
+            .map_err(stage_failed("Emit assembly"))?;
+        Ok((!self.assembly_is_output()).then_some(path))
     }
 }
 
-fn gcc(options: &[&str]) -> Result<(), String> {
-    println!("gcc{}", options.iter().map(|op| format!(" {op}")).collect::<String>());
+// Errors of the stages that don't report diagnostics themselves.
+fn stage_failed(stage: &str) -> impl Fn(String) -> Vec<Diagnostic> + '_ {
+    move |e| vec![Diagnostic::error(format!("`{stage}` stage failed: {e}"))]
+}
+
+fn toolchain(program: &str, options: &[&OsStr], verbose: bool) -> Result<(), String> {
+    if interrupted() {
+        return Err("interrupted".into());
+    }
+    if verbose {
+        eprintln!("{program}{}", options.iter().map(|op| format!(" {}", op.to_string_lossy())).collect::<String>());
+    }
 
-    let output = Command::new("gcc").args(options).output()
-        .map_err(|e| format!("Failed to execute gcc process: {e}"))?;
+    let output = Command::new(program).args(options).output()
+        .map_err(|e| format!("Failed to execute {program} process: {e}"))?;
 
     if !output.stdout.is_empty() {
         println!("{}", String::from_utf8(output.stdout).unwrap_or("GCC stdout isn't UTF-8".into()));
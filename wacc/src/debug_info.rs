@@ -0,0 +1,297 @@
+//! # DWARF debug info
+//!
+//! What `-g` adds to the x86-64 assembly so that gdb can step through and inspect the program:
+//! - `.file` and `.loc` directives, from which the assembler builds the line table in `.debug_line`;
+//! - CFI directives, from which it builds `.eh_frame`, describing the frame set up around `%rbp` and the callee-saved
+//!   registers pushed after it;
+//! - `.debug_abbrev` and `.debug_info` in DWARF 4, written out as data: the compile unit, the `int` type, and the
+//!   function with the locals that live in its frame, at their offsets from `%rbp`.
+//!
+//! Functions take no parameters and declare no variables yet, so the only locals are TACKY temporaries, marked as
+//! artificial. Those the register allocator keeps in registers have no location: their register changes from one
+//! instruction to the next.
+
+use std::fmt::Write;
+use std::rc::Rc;
+
+use crate::ast_nodes::*;
+use crate::frame::FrameLayout;
+use crate::span::Span;
+
+const DW_TAG_BASE_TYPE: u8 = 0x24;
+const DW_TAG_COMPILE_UNIT: u8 = 0x11;
+const DW_TAG_SUBPROGRAM: u8 = 0x2e;
+const DW_TAG_VARIABLE: u8 = 0x34;
+
+const DW_AT_LOCATION: u8 = 0x02;
+const DW_AT_NAME: u8 = 0x03;
+const DW_AT_BYTE_SIZE: u8 = 0x0b;
+const DW_AT_STMT_LIST: u8 = 0x10;
+const DW_AT_LOW_PC: u8 = 0x11;
+const DW_AT_HIGH_PC: u8 = 0x12;
+const DW_AT_LANGUAGE: u8 = 0x13;
+const DW_AT_COMP_DIR: u8 = 0x1b;
+const DW_AT_PRODUCER: u8 = 0x25;
+const DW_AT_PROTOTYPED: u8 = 0x27;
+const DW_AT_ARTIFICIAL: u8 = 0x34;
+const DW_AT_DECL_COLUMN: u8 = 0x39;
+const DW_AT_DECL_FILE: u8 = 0x3a;
+const DW_AT_DECL_LINE: u8 = 0x3b;
+const DW_AT_ENCODING: u8 = 0x3e;
+const DW_AT_EXTERNAL: u8 = 0x3f;
+const DW_AT_FRAME_BASE: u8 = 0x40;
+const DW_AT_TYPE: u8 = 0x49;
+
+const DW_FORM_ADDR: u8 = 0x01;
+const DW_FORM_DATA8: u8 = 0x07;
+const DW_FORM_STRING: u8 = 0x08;
+const DW_FORM_DATA1: u8 = 0x0b;
+const DW_FORM_UDATA: u8 = 0x0f;
+const DW_FORM_REF4: u8 = 0x13;
+const DW_FORM_SEC_OFFSET: u8 = 0x17;
+const DW_FORM_EXPRLOC: u8 = 0x18;
+const DW_FORM_FLAG_PRESENT: u8 = 0x19;
+
+const DW_LANG_C99: u8 = 0x0c;
+const DW_ATE_SIGNED: u8 = 0x05;
+const DW_OP_FBREG: u8 = 0x91;
+const DW_OP_RBP: u8 = 0x56;
+
+const RBP: u8 = 6;
+const RSP: u8 = 7;
+
+const COMPILE_UNIT_ABBREV: u8 = 1;
+const SUBPROGRAM_ABBREV: u8 = 2;
+const VARIABLE_ABBREV: u8 = 3;
+const BASE_TYPE_ABBREV: u8 = 4;
+
+// The code, tag, whether DIEs have children, and the attributes with their forms.
+type Abbreviation = (u8, u8, bool, &'static [(u8, u8)]);
+
+const ABBREVIATIONS: [Abbreviation; 4] = [
+    (COMPILE_UNIT_ABBREV, DW_TAG_COMPILE_UNIT, true, &[
+        (DW_AT_PRODUCER, DW_FORM_STRING), (DW_AT_LANGUAGE, DW_FORM_DATA1), (DW_AT_NAME, DW_FORM_STRING),
+        (DW_AT_COMP_DIR, DW_FORM_STRING), (DW_AT_LOW_PC, DW_FORM_ADDR), (DW_AT_HIGH_PC, DW_FORM_DATA8),
+        (DW_AT_STMT_LIST, DW_FORM_SEC_OFFSET),
+    ]),
+    (SUBPROGRAM_ABBREV, DW_TAG_SUBPROGRAM, true, &[
+        (DW_AT_EXTERNAL, DW_FORM_FLAG_PRESENT), (DW_AT_NAME, DW_FORM_STRING), (DW_AT_DECL_FILE, DW_FORM_UDATA),
+        (DW_AT_DECL_LINE, DW_FORM_UDATA), (DW_AT_DECL_COLUMN, DW_FORM_UDATA),
+        (DW_AT_PROTOTYPED, DW_FORM_FLAG_PRESENT), (DW_AT_TYPE, DW_FORM_REF4), (DW_AT_LOW_PC, DW_FORM_ADDR),
+        (DW_AT_HIGH_PC, DW_FORM_DATA8), (DW_AT_FRAME_BASE, DW_FORM_EXPRLOC),
+    ]),
+    (VARIABLE_ABBREV, DW_TAG_VARIABLE, false, &[
+        (DW_AT_NAME, DW_FORM_STRING), (DW_AT_ARTIFICIAL, DW_FORM_FLAG_PRESENT), (DW_AT_TYPE, DW_FORM_REF4),
+        (DW_AT_LOCATION, DW_FORM_EXPRLOC),
+    ]),
+    (BASE_TYPE_ABBREV, DW_TAG_BASE_TYPE, false, &[
+        (DW_AT_BYTE_SIZE, DW_FORM_DATA1), (DW_AT_ENCODING, DW_FORM_DATA1), (DW_AT_NAME, DW_FORM_STRING),
+    ]),
+];
+
+// The register numbers of the System V x86-64 psABI.
+fn dwarf_register(reg: AsmReg) -> u8 {
+    match reg {
+        asm::AX => 0,
+        asm::DX => 1,
+        asm::CX => 2,
+        asm::BX => 3,
+        asm::SI => 4,
+        asm::DI => 5,
+        asm::R8 => 8,
+        asm::R9 => 9,
+        asm::R10 => 10,
+        asm::R11 => 11,
+        asm::R12 => 12,
+        asm::R13 => 13,
+        asm::R14 => 14,
+        asm::R15 => 15,
+    }
+}
+
+fn instruction_span(instruction: &AsmInstruction) -> &Span {
+    match instruction {
+        asm::Mov(_, _, span) | asm::Unary(_, _, span) | asm::Binary(_, _, _, span) | asm::AllocateStack(_, span)
+        | asm::Push(_, span) | asm::Pop(_, span) | asm::Ret(span) => span,
+    }
+}
+
+fn quoted(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn sleb128_len(mut value: i64) -> usize {
+    let mut len = 1;
+    while !(-64..64).contains(&value) {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+/// The state of the debug info while one function is emitted. Its methods return directives without indentation.
+pub struct DebugInfo {
+    files: Vec<Rc<str>>,
+    location: Option<(usize, u32, u32)>,
+    frame_size: u32,
+    pushed: u32,
+}
+
+impl DebugInfo {
+    /// Numbers the files of the spans in `function_definition`, which must be declared before any `.loc` uses them.
+    pub fn new(function_definition: &AsmFunctionDefinition) -> Self {
+        let asm::Function(_, instructions, _, span) = function_definition;
+        let mut files: Vec<Rc<str>> = Vec::new();
+        for span in std::iter::once(span).chain(instructions.iter().map(instruction_span)) {
+            if !files.contains(&span.file) {
+                files.push(span.file.clone());
+            }
+        }
+        Self { files, location: None, frame_size: 0, pushed: 0 }
+    }
+
+    pub fn start(&self, symbol: &str) -> Vec<String> {
+        let mut directives: Vec<String> = self.files.iter()
+            .enumerate()
+            .map(|(i, file)| format!(".file {} {}", i + 1, quoted(file)))
+            .collect();
+        directives.push(format!(".type {symbol}, @function"));
+        directives
+    }
+
+    fn file_number(&self, span: &Span) -> usize {
+        self.files.iter().position(|file| *file == span.file).unwrap() + 1
+    }
+
+    /// A `.loc` for `instruction`, unless the previous one is for the same place.
+    pub fn loc(&mut self, instruction: &AsmInstruction) -> Option<String> {
+        self.loc_of_span(instruction_span(instruction))
+    }
+
+    fn loc_of_span(&mut self, span: &Span) -> Option<String> {
+        let location = (self.file_number(span), span.line, span.column);
+        if self.location == Some(location) {
+            return None;
+        }
+        self.location = Some(location);
+        Some(format!(".loc {} {} {}", location.0, location.1, location.2))
+    }
+
+    pub fn prologue(&mut self, span: &Span) -> Vec<String> {
+        self.loc_of_span(span).into_iter().chain([".cfi_startproc".into()]).collect()
+    }
+
+    /// After `push %rbp`, the return address and the saved `%rbp` sit above the stack pointer.
+    pub fn after_push_rbp(&self) -> Vec<String> {
+        vec![".cfi_def_cfa_offset 16".into(), format!(".cfi_offset {RBP}, -16")]
+    }
+
+    /// After `mov %rsp, %rbp`, the frame is found from `%rbp` whatever happens to the stack pointer.
+    pub fn after_set_frame_pointer(&self) -> Vec<String> {
+        vec![format!(".cfi_def_cfa_register {RBP}")]
+    }
+
+    pub fn after_allocate_stack(&mut self, bytes: u32) {
+        self.frame_size += bytes;
+    }
+
+    /// Callee-saved registers are pushed right below the frame, after it's allocated.
+    pub fn after_push(&mut self, reg: AsmReg) -> String {
+        self.pushed += 1;
+        let offset = 16 + self.frame_size + 8 * self.pushed;
+        format!(".cfi_offset {}, -{offset}", dwarf_register(reg))
+    }
+
+    /// Returns the directives around the epilogue: before `mov %rbp, %rsp`, and after `pop %rbp`. The frame is
+    /// described again for any code after the `ret`.
+    pub fn epilogue(&self) -> (String, String) {
+        (".cfi_remember_state".into(), format!(".cfi_def_cfa {RSP}, 8"))
+    }
+
+    pub fn after_ret(&self) -> String {
+        ".cfi_restore_state".into()
+    }
+
+    /// Closes the function `symbol`, and describes it in the `.debug_abbrev` and `.debug_info` sections.
+    pub fn end(&self, symbol: &str, function_definition: &AsmFunctionDefinition) -> String {
+        let asm::Function(asm::Identifier(name), _, frame_layout, span) = function_definition;
+        let end_label = ".Lfunc_end0";
+        let mut asm_code = String::new();
+        asm_code.push_str("\t.cfi_endproc\n");
+        asm_code.push_str(&format!("{end_label}:\n"));
+        asm_code.push_str(&format!("\t.size {symbol}, {end_label}-{symbol}\n"));
+        asm_code.push_str("\t.section .debug_abbrev,\"\",@progbits\n");
+        asm_code.push_str(".Ldebug_abbrev0:\n");
+        for (code, tag, has_children, attributes) in ABBREVIATIONS {
+            writeln!(asm_code, "\t.uleb128 {code}\n\t.uleb128 {tag:#x}\n\t.byte {}", has_children as u8).unwrap();
+            for (attribute, form) in attributes {
+                writeln!(asm_code, "\t.uleb128 {attribute:#x}\n\t.uleb128 {form:#x}").unwrap();
+            }
+            asm_code.push_str("\t.byte 0\n\t.byte 0\n");
+        }
+        asm_code.push_str("\t.byte 0\n");
+
+        let comp_dir = std::env::current_dir().map(|dir| dir.to_string_lossy().into_owned()).unwrap_or_default();
+        let high_pc = format!("{end_label}-{symbol}");
+        let int_type = ".Ldebug_type_int-.Ldebug_info0";
+        let mut info = vec![
+            ".long .Ldebug_info_end0-.Ldebug_info_start0".into(),
+            ".Ldebug_info_start0:".into(),
+            ".value 4".into(),
+            ".long .Ldebug_abbrev0".into(),
+            ".byte 8".into(),
+            format!(".uleb128 {COMPILE_UNIT_ABBREV}"),
+            format!(".string {}", quoted(&format!("wacc {}", env!("CARGO_PKG_VERSION")))),
+            format!(".byte {DW_LANG_C99:#x}"),
+            format!(".string {}", quoted(&span.file)),
+            format!(".string {}", quoted(&comp_dir)),
+            format!(".quad {symbol}"),
+            format!(".quad {high_pc}"),
+            ".long .Ldebug_line0".into(),
+            ".Ldebug_type_int:".into(),
+            format!(".uleb128 {BASE_TYPE_ABBREV}"),
+            ".byte 4".into(),
+            format!(".byte {DW_ATE_SIGNED:#x}"),
+            ".string \"int\"".into(),
+            format!(".uleb128 {SUBPROGRAM_ABBREV}"),
+            format!(".string {}", quoted(name)),
+            format!(".uleb128 {}", self.file_number(span)),
+            format!(".uleb128 {}", span.line),
+            format!(".uleb128 {}", span.column),
+            format!(".long {int_type}"),
+            format!(".quad {symbol}"),
+            format!(".quad {high_pc}"),
+            ".uleb128 1".into(),
+            format!(".byte {DW_OP_RBP:#x}"),
+        ];
+        info.extend(variables(frame_layout, int_type));
+        info.extend([".byte 0".into(), ".byte 0".into(), ".Ldebug_info_end0:".into()]);
+
+        asm_code.push_str("\t.section .debug_info,\"\",@progbits\n");
+        asm_code.push_str(".Ldebug_info0:\n");
+        for line in info {
+            if line.ends_with(':') {
+                writeln!(asm_code, "{line}").unwrap();
+            } else {
+                writeln!(asm_code, "\t{line}").unwrap();
+            }
+        }
+        // The assembler appends the line table built from the `.loc`s to this section.
+        asm_code.push_str("\t.section .debug_line,\"\",@progbits\n");
+        asm_code.push_str(".Ldebug_line0:\n");
+        asm_code
+    }
+}
+
+fn variables(frame_layout: &FrameLayout, int_type: &str) -> Vec<String> {
+    let mut info = Vec::new();
+    for slot in frame_layout.slots() {
+        info.push(format!(".uleb128 {VARIABLE_ABBREV}"));
+        info.push(format!(".string {}", quoted(&slot.name)));
+        info.push(format!(".long {int_type}"));
+        info.push(format!(".uleb128 {}", 1 + sleb128_len(slot.offset as i64)));
+        info.push(format!(".byte {DW_OP_FBREG:#x}"));
+        info.push(format!(".sleb128 {}", slot.offset));
+    }
+    info
+}
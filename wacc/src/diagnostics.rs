@@ -0,0 +1,305 @@
+//! # Diagnostics
+//!
+//! Errors and warnings with source locations, rendered like rustc does:
+//! ```text
+//! error[E0100]: expected `;`, found `}`
+//!  --> main.c:3:1
+//!   |
+//! 2 |     return 2
+//!   |            - expected `;` after this
+//! 3 | }
+//!   | ^ unexpected `}`
+//! ```
+//! Snippets are read back from the files the spans point at; a diagnostic without spans renders as its first line only.
+//!
+//! For tools, `--error-format=json` prints one JSON object per diagnostic and line, and `--error-format=sarif` a single
+//! SARIF 2.1.0 log. Ranges in both are 1-based, and their end column is exclusive.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+use crate::span::Span;
+
+pub const INVALID_TOKEN: &str = "E0001";
+pub const MALFORMED_LINE_MARKER: &str = "E0002";
+pub const UNEXPECTED_TOKEN: &str = "E0100";
+pub const UNEXPECTED_END_OF_INPUT: &str = "E0101";
+pub const EXPECTED_EXPRESSION: &str = "E0102";
+pub const TRAILING_TOKENS: &str = "E0103";
+pub const MALFORMED_TACKY: &str = "E0200";
+pub const UNDEFINED_TACKY_VARIABLE: &str = "E0201";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    Json,
+    Sarif,
+}
+
+impl TryFrom<&str> for ErrorFormat {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!("Unknown error format `{value}`, expected `human`, `json` or `sarif`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("error"),
+            Self::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, ..Self::error(message) }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.retain(|label| !label.primary);
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        self
+    }
+
+    pub fn primary(&self) -> Option<&Label> {
+        self.labels.iter().find(|label| label.primary)
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut rendered = match self.code {
+            Some(code) => format!("{}[{code}]: {}\n", self.severity, self.message),
+            None => format!("{}: {}\n", self.severity, self.message),
+        };
+        let Some(primary) = self.primary() else {
+            for note in &self.notes {
+                rendered.push_str(&format!("  = note: {note}\n"));
+            }
+            if let Some(help) = &self.help {
+                rendered.push_str(&format!("  = help: {help}\n"));
+            }
+            return rendered;
+        };
+
+        let mut labels: Vec<&Label> = self.labels.iter().collect();
+        labels.sort_by_key(|label| (label.span.file != primary.span.file, label.span.file.clone(), label.span.line, label.span.column));
+        let gutter = labels.iter().map(|label| label.span.line.to_string().len()).max().unwrap_or(1);
+        let pad = " ".repeat(gutter);
+
+        let mut sources = SourceCache::default();
+        rendered.push_str(&format!("{pad}--> {}\n", primary.span));
+        let mut current_file = primary.span.file.clone();
+        rendered.push_str(&format!("{pad} |\n"));
+        for label in labels {
+            if label.span.file != current_file {
+                rendered.push_str(&format!("{pad}::: {}\n{pad} |\n", label.span));
+                current_file = label.span.file.clone();
+            }
+            let Some(line) = sources.line(&label.span) else {
+                rendered.push_str(&format!("{pad} = {}\n", label.message));
+                continue;
+            };
+            let start = (label.span.column as usize).saturating_sub(1).min(line.len());
+            let width = label.span.len.clamp(1, (line.len() - start).max(1));
+            // Columns count bytes, while the underline goes under characters.
+            let (start, width) = (
+                line.char_indices().take_while(|&(i, _)| i < start).count(),
+                line.char_indices().filter(|&(i, _)| (start..start + width).contains(&i)).count().max(1),
+            );
+            rendered.push_str(&format!("{:>gutter$} | {line}\n", label.span.line));
+            let underline = if label.primary { "^" } else { "-" }.repeat(width);
+            let message = if label.message.is_empty() { String::new() } else { format!(" {}", label.message) };
+            rendered.push_str(&format!("{pad} | {}{underline}{message}\n", " ".repeat(start)));
+        }
+        if !self.notes.is_empty() || self.help.is_some() {
+            rendered.push_str(&format!("{pad} |\n"));
+        }
+        for note in &self.notes {
+            rendered.push_str(&format!("{pad} = note: {note}\n"));
+        }
+        if let Some(help) = &self.help {
+            rendered.push_str(&format!("{pad} = help: {help}\n"));
+        }
+        rendered
+    }
+}
+
+impl Label {
+    // Spans don't record where they end, so a range stays on its start line.
+    fn range(&self) -> (u32, u32, u32, u32) {
+        let span = &self.span;
+        (span.line, span.column, span.line, span.column + span.len.max(1) as u32)
+    }
+
+    fn to_json(&self) -> String {
+        let (start_line, start_column, end_line, end_column) = self.range();
+        format!(
+            "{{\"file\":{},\"range\":{{\"start\":{{\"line\":{start_line},\"column\":{start_column}}},\"end\":{{\"line\":{end_line},\"column\":{end_column}}}}},\"message\":{},\"primary\":{}}}",
+            json_string(&self.span.file), json_string(&self.message), self.primary,
+        )
+    }
+
+    fn to_sarif_location(&self) -> String {
+        let (start_line, start_column, end_line, end_column) = self.range();
+        format!(
+            "{{\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}},\"region\":{{\"startLine\":{start_line},\"startColumn\":{start_column},\"endLine\":{end_line},\"endColumn\":{end_column}}}}},\"message\":{{\"text\":{}}}}}",
+            json_string(&self.span.file), json_string(&self.message),
+        )
+    }
+}
+
+impl Diagnostic {
+    pub fn to_json(&self) -> String {
+        let primary = self.primary();
+        let labels: Vec<String> = self.labels.iter().map(Label::to_json).collect();
+        let notes: Vec<String> = self.notes.iter().map(|note| json_string(note)).collect();
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"file\":{},\"range\":{},\"labels\":[{}],\"notes\":[{}],\"help\":{}}}",
+            json_string(&self.severity.to_string()),
+            self.code.map(json_string).unwrap_or("null".into()),
+            json_string(&self.message),
+            primary.map(|label| json_string(&label.span.file)).unwrap_or("null".into()),
+            primary.map(|label| {
+                let (start_line, start_column, end_line, end_column) = label.range();
+                format!("{{\"start\":{{\"line\":{start_line},\"column\":{start_column}}},\"end\":{{\"line\":{end_line},\"column\":{end_column}}}}}")
+            }).unwrap_or("null".into()),
+            labels.join(","),
+            notes.join(","),
+            self.help.as_deref().map(json_string).unwrap_or("null".into()),
+        )
+    }
+
+    fn to_sarif_result(&self) -> String {
+        let mut result = String::from("{");
+        if let Some(code) = self.code {
+            result.push_str(&format!("\"ruleId\":{},", json_string(code)));
+        }
+        result.push_str(&format!("\"level\":{},\"message\":{{\"text\":{}}}", json_string(&self.severity.to_string()), json_string(&self.message)));
+        let primary: Vec<String> = self.primary().map(Label::to_sarif_location).into_iter().collect();
+        let related: Vec<String> = self.labels.iter().filter(|label| !label.primary).map(Label::to_sarif_location).collect();
+        result.push_str(&format!(",\"locations\":[{}],\"relatedLocations\":[{}]", primary.join(","), related.join(",")));
+        let notes: Vec<String> = self.notes.iter().map(|note| json_string(note)).collect();
+        let help = self.help.as_deref().map(json_string).unwrap_or("null".into());
+        result.push_str(&format!(",\"properties\":{{\"notes\":[{}],\"help\":{help}}}}}", notes.join(",")));
+        result
+    }
+}
+
+/// Renders `diagnostics` for stderr in the given format.
+pub fn render_diagnostics(diagnostics: &[Diagnostic], format: ErrorFormat) -> String {
+    match format {
+        ErrorFormat::Human => diagnostics.iter().map(|diagnostic| format!("{}\n", diagnostic.render())).collect(),
+        ErrorFormat::Json => diagnostics.iter().map(|diagnostic| format!("{}\n", diagnostic.to_json())).collect(),
+        ErrorFormat::Sarif => {
+            let mut rules: Vec<&str> = diagnostics.iter().filter_map(|diagnostic| diagnostic.code).collect();
+            rules.sort();
+            rules.dedup();
+            let rules: Vec<String> = rules.into_iter().map(|code| format!("{{\"id\":{}}}", json_string(code))).collect();
+            let results: Vec<String> = diagnostics.iter().map(Diagnostic::to_sarif_result).collect();
+            format!(
+                "{{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",\"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"wacc\",\"version\":{},\"rules\":[{}]}}}},\"results\":[{}]}}]}}\n",
+                json_string(env!("CARGO_PKG_VERSION")), rules.join(","), results.join(","),
+            )
+        },
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+impl From<String> for Diagnostic {
+    fn from(message: String) -> Self {
+        Self::error(message)
+    }
+}
+
+#[derive(Default)]
+struct SourceCache {
+    files: HashMap<String, Option<String>>,
+}
+
+impl SourceCache {
+    fn line(&mut self, span: &Span) -> Option<String> {
+        let source = self.files.entry(span.file.to_string())
+            .or_insert_with(|| fs::read_to_string(&*span.file).ok());
+        let line = source.as_ref()?.lines().nth((span.line as usize).checked_sub(1)?)?;
+        Some(line.replace('\t', " "))
+    }
+}
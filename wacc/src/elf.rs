@@ -0,0 +1,466 @@
+//! # ELF64 relocatable objects
+//!
+//! Writes an `ObjectFile` as an x86-64 ELF `.o` file that the system linker accepts. The sections are, in order:
+//! ```text
+//! [0] null  [1] .text  [2] .rela.text  [3] .symtab  [4] .strtab  [5] .shstrtab  [6] .note.GNU-stack
+//! ```
+//! The symbol table holds the null symbol, a section symbol for `.text`, then the global functions defined in `.text`,
+//! then the undefined symbols that relocations refer to. The empty `.note.GNU-stack` section asks for a non-executable
+//! stack, like gcc's output does.
+//!
+//! `read_object_file` reads such objects back for the linker, along with gas's, as long as everything they allocate
+//! is in `.text`. `write_executable` writes a static executable with a single loadable segment for code, which needs
+//! no section headers.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct ObjectFile {
+    pub text: Vec<u8>,
+    pub symbols: Vec<Symbol>,
+    pub relocations: Vec<Relocation>,
+}
+
+/// A global function defined in `.text`.
+#[derive(Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// A 32-bit field of `.text` to patch with the address of `symbol`, relative to the field itself.
+#[derive(Debug)]
+pub struct Relocation {
+    pub offset: u64,
+    pub symbol: String,
+    pub kind: RelocationKind,
+    pub addend: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelocationKind {
+    Pc32,
+    Plt32,
+}
+
+impl RelocationKind {
+    pub(crate) fn number(&self) -> u32 {
+        match self {
+            Self::Pc32 => 2,
+            Self::Plt32 => 4,
+        }
+    }
+}
+
+impl TryFrom<u32> for RelocationKind {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            2 => Ok(Self::Pc32),
+            4 => Ok(Self::Plt32),
+            _ => Err(format!("Unsupported relocation type {value}")),
+        }
+    }
+}
+
+const ELF_HEADER_SIZE: u64 = 64;
+const PROGRAM_HEADER_SIZE: u64 = 56;
+const SECTION_HEADER_SIZE: u64 = 64;
+const SYMBOL_SIZE: u64 = 24;
+const RELA_SIZE: u64 = 24;
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_RELA: u32 = 4;
+const SHF_ALLOC: u64 = 0x2;
+const SHN_UNDEF: u16 = 0;
+const SHF_EXECINSTR: u64 = 0x4;
+const SHF_INFO_LINK: u64 = 0x40;
+
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const EM_X86_64: u16 = 62;
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 0x1;
+const PF_R: u32 = 0x4;
+const PAGE_SIZE: u64 = 0x1000;
+
+const TEXT_INDEX: u16 = 1;
+const SYMTAB_INDEX: u32 = 3;
+const STRTAB_INDEX: u32 = 4;
+const SHSTRTAB_INDEX: u16 = 5;
+
+const STB_LOCAL: u8 = 0;
+const STB_GLOBAL: u8 = 1;
+const STT_NOTYPE: u8 = 0;
+const STT_FUNC: u8 = 2;
+const STT_SECTION: u8 = 3;
+
+struct StringTable {
+    bytes: Vec<u8>,
+}
+
+impl StringTable {
+    fn new() -> Self {
+        Self { bytes: vec![0] }
+    }
+
+    fn add(&mut self, string: &str) -> u32 {
+        let offset = self.bytes.len() as u32;
+        self.bytes.extend(string.as_bytes());
+        self.bytes.push(0);
+        offset
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn align(&mut self, alignment: usize) {
+        self.bytes.resize(self.bytes.len().next_multiple_of(alignment), 0);
+    }
+
+    // Appends `contents` at the next multiple of `alignment`, and returns their offset.
+    fn place(&mut self, contents: &[u8], alignment: usize) -> u64 {
+        self.align(alignment);
+        let offset = self.bytes.len() as u64;
+        self.bytes.extend(contents);
+        offset
+    }
+
+    // The fields of the ELF header up to the size of program headers, which are the same for every file type.
+    fn elf_header(&mut self, kind: u16, entry: u64, program_headers_offset: u64, section_headers_offset: u64) {
+        self.bytes.extend(b"\x7fELF");
+        self.u8(2); // 64-bit
+        self.u8(1); // little-endian
+        self.u8(1); // ELF version 1
+        self.bytes.resize(16, 0);
+        self.u16(kind);
+        self.u16(EM_X86_64);
+        self.u32(1);
+        self.u64(entry);
+        self.u64(program_headers_offset);
+        self.u64(section_headers_offset);
+        self.u32(0); // flags
+        self.u16(ELF_HEADER_SIZE as u16);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn section_header(&mut self, name: u32, kind: u32, flags: u64, offset: u64, size: u64, link: u32, info: u32, alignment: u64, entry_size: u64) {
+        self.u32(name);
+        self.u32(kind);
+        self.u64(flags);
+        self.u64(0);
+        self.u64(offset);
+        self.u64(size);
+        self.u32(link);
+        self.u32(info);
+        self.u64(alignment);
+        self.u64(entry_size);
+    }
+}
+
+pub fn write_object_file(object: &ObjectFile) -> Vec<u8> {
+    let mut strtab = StringTable::new();
+    let mut symtab = Writer::default();
+    // The null symbol, then the section symbol of `.text`.
+    symtab.bytes.resize(SYMBOL_SIZE as usize, 0);
+    write_symbol(&mut symtab, 0, STB_LOCAL << 4 | STT_SECTION, TEXT_INDEX, 0, 0);
+    let first_global = 2;
+
+    let mut symbol_indices = HashMap::new();
+    for symbol in &object.symbols {
+        symbol_indices.insert(symbol.name.as_str(), first_global + symbol_indices.len() as u32);
+        write_symbol(&mut symtab, strtab.add(&symbol.name), STB_GLOBAL << 4 | STT_FUNC, TEXT_INDEX, symbol.offset, symbol.size);
+    }
+    for relocation in &object.relocations {
+        if !symbol_indices.contains_key(relocation.symbol.as_str()) {
+            symbol_indices.insert(relocation.symbol.as_str(), first_global + symbol_indices.len() as u32);
+            write_symbol(&mut symtab, strtab.add(&relocation.symbol), STB_GLOBAL << 4 | STT_NOTYPE, 0, 0, 0);
+        }
+    }
+
+    let mut rela = Writer::default();
+    for relocation in &object.relocations {
+        rela.u64(relocation.offset);
+        rela.u64((symbol_indices[relocation.symbol.as_str()] as u64) << 32 | relocation.kind.number() as u64);
+        rela.u64(relocation.addend as u64);
+    }
+
+    let mut shstrtab = StringTable::new();
+    let names = [".text", ".rela.text", ".symtab", ".strtab", ".shstrtab", ".note.GNU-stack"].map(|name| shstrtab.add(name));
+
+    let mut file = Writer::default();
+    file.bytes.resize(ELF_HEADER_SIZE as usize, 0);
+    let text_offset = file.place(&object.text, 16);
+    let rela_offset = file.place(&rela.bytes, 8);
+    let symtab_offset = file.place(&symtab.bytes, 8);
+    let strtab_offset = file.place(&strtab.bytes, 1);
+    let shstrtab_offset = file.place(&shstrtab.bytes, 1);
+    file.align(8);
+    let section_headers_offset = file.bytes.len() as u64;
+
+    file.bytes.resize(file.bytes.len() + SECTION_HEADER_SIZE as usize, 0);
+    file.section_header(names[0], SHT_PROGBITS, SHF_ALLOC | SHF_EXECINSTR, text_offset, object.text.len() as u64, 0, 0, 16, 0);
+    file.section_header(names[1], SHT_RELA, SHF_INFO_LINK, rela_offset, rela.bytes.len() as u64, SYMTAB_INDEX, TEXT_INDEX as u32, 8, RELA_SIZE);
+    file.section_header(names[2], SHT_SYMTAB, 0, symtab_offset, symtab.bytes.len() as u64, STRTAB_INDEX, first_global, 8, SYMBOL_SIZE);
+    file.section_header(names[3], SHT_STRTAB, 0, strtab_offset, strtab.bytes.len() as u64, 0, 0, 1, 0);
+    file.section_header(names[4], SHT_STRTAB, 0, shstrtab_offset, shstrtab.bytes.len() as u64, 0, 0, 1, 0);
+    file.section_header(names[5], SHT_PROGBITS, 0, shstrtab_offset + shstrtab.bytes.len() as u64, 0, 0, 0, 1, 0);
+
+    let mut header = Writer::default();
+    header.elf_header(ET_REL, 0, 0, section_headers_offset);
+    header.u16(0);
+    header.u16(0);
+    header.u16(SECTION_HEADER_SIZE as u16);
+    header.u16(7);
+    header.u16(SHSTRTAB_INDEX);
+    file.bytes[..ELF_HEADER_SIZE as usize].copy_from_slice(&header.bytes);
+    file.bytes
+}
+
+/// Writes an executable that loads `text` at `address` and starts at `entry`.
+pub fn write_executable(text: &[u8], address: u64, entry: u64) -> Vec<u8> {
+    let mut file = Writer::default();
+    file.elf_header(ET_EXEC, entry, ELF_HEADER_SIZE, 0);
+    file.u16(PROGRAM_HEADER_SIZE as u16);
+    file.u16(1);
+    file.u16(SECTION_HEADER_SIZE as u16);
+    file.u16(0);
+    file.u16(0);
+
+    // The segment starts at the beginning of the file, so that its offset and address agree modulo the page size.
+    let size = executable_text_offset() + text.len() as u64;
+    file.u32(PT_LOAD);
+    file.u32(PF_R | PF_X);
+    file.u64(0);
+    file.u64(address - executable_text_offset());
+    file.u64(address - executable_text_offset());
+    file.u64(size);
+    file.u64(size);
+    file.u64(PAGE_SIZE);
+    file.place(text, 16);
+    file.bytes
+}
+
+/// Where `write_executable` puts the code in the file, so a linker can place it at a matching address.
+pub fn executable_text_offset() -> u64 {
+    (ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE).next_multiple_of(16)
+}
+
+pub fn read_object_file(bytes: &[u8]) -> Result<ObjectFile, String> {
+    let file = Reader { bytes };
+    if bytes.get(..4) != Some(b"\x7fELF") || file.u8(4)? != 2 || file.u8(5)? != 1 {
+        return Err("not a little-endian ELF64 file".into());
+    }
+    if file.u16(16)? != ET_REL || file.u16(18)? != EM_X86_64 {
+        return Err("not an x86-64 relocatable object".into());
+    }
+    let section_headers_offset = file.u64(40)?;
+    let section_count = file.u16(60)? as u64;
+    let shstrtab = file.section(section_headers_offset, file.u16(62)? as u64)?;
+    let sections: Vec<Section> = (0..section_count)
+        .map(|index| file.section(section_headers_offset, index))
+        .collect::<Result<_, _>>()?;
+
+    let mut object = ObjectFile::default();
+    let mut text_index = None;
+    for (index, section) in sections.iter().enumerate() {
+        let name = file.string(shstrtab.offset, section.name)?;
+        if section.flags & SHF_ALLOC == 0 || section.size == 0 {
+            continue;
+        }
+        if name != ".text" || section.kind != SHT_PROGBITS || text_index.is_some() {
+            return Err(format!("unsupported section `{name}`"));
+        }
+        text_index = Some(index);
+        object.text = file.slice(section.offset, section.size)?.to_vec();
+    }
+    let Some(symtab) = sections.iter().find(|section| section.kind == SHT_SYMTAB) else {
+        return Ok(object);
+    };
+    let strtab = sections.get(symtab.link as usize).ok_or("invalid string table index")?;
+
+    let symbols: Vec<(String, u8, u16, u64, u64)> = (0..symtab.size / SYMBOL_SIZE)
+        .map(|index| {
+            let symbol = file.entry(symtab.offset, index, SYMBOL_SIZE)?;
+            let name = file.string(strtab.offset, symbol.u32(0)?)?;
+            Ok((name, symbol.u8(4)?, symbol.u16(6)?, symbol.u64(8)?, symbol.u64(16)?))
+        })
+        .collect::<Result<_, String>>()?;
+    for (name, info, section, offset, size) in &symbols {
+        if info >> 4 == STB_GLOBAL && *section != SHN_UNDEF {
+            if Some(*section as usize) != text_index {
+                return Err(format!("symbol `{name}` is outside `.text`"));
+            }
+            object.symbols.push(Symbol { name: name.clone(), offset: *offset, size: *size });
+        }
+    }
+
+    for rela in sections.iter().filter(|section| section.kind == SHT_RELA) {
+        if Some(rela.info as usize) != text_index {
+            continue;
+        }
+        for index in 0..rela.size / RELA_SIZE {
+            let entry = file.entry(rela.offset, index, RELA_SIZE)?;
+            let info = entry.u64(8)?;
+            let (name, symbol_info, ..) = symbols.get((info >> 32) as usize).ok_or("invalid symbol index")?;
+            if symbol_info >> 4 != STB_GLOBAL {
+                return Err(format!("unsupported relocation against local symbol `{name}`"));
+            }
+            object.relocations.push(Relocation {
+                offset: entry.u64(0)?,
+                symbol: name.clone(),
+                kind: RelocationKind::try_from(info as u32)?,
+                addend: entry.u64(16)? as i64,
+            });
+        }
+    }
+    Ok(object)
+}
+
+fn write_symbol(symtab: &mut Writer, name: u32, info: u8, section: u16, value: u64, size: u64) {
+    symtab.u32(name);
+    symtab.u8(info);
+    symtab.u8(0);
+    symtab.u16(section);
+    symtab.u64(value);
+    symtab.u64(size);
+}
+
+struct Section {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    info: u32,
+}
+
+// Bounds-checked little-endian reads, which fail on truncated files.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn slice(&self, offset: u64, size: u64) -> Result<&[u8], String> {
+        usize::try_from(offset).ok()
+            .zip(usize::try_from(size).ok())
+            .and_then(|(offset, size)| self.bytes.get(offset..offset.checked_add(size)?))
+            .ok_or_else(|| "truncated file".into())
+    }
+
+    fn array<const N: usize>(&self, offset: u64) -> Result<[u8; N], String> {
+        Ok(self.slice(offset, N as u64)?.try_into().unwrap())
+    }
+
+    fn u8(&self, offset: u64) -> Result<u8, String> {
+        Ok(self.array::<1>(offset)?[0])
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.array(offset)?))
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array(offset)?))
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array(offset)?))
+    }
+
+    // The string at `index` in the string table at `table_offset`.
+    fn string(&self, table_offset: u64, index: u32) -> Result<String, String> {
+        let tail = table_offset.checked_add(index as u64)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .and_then(|offset| self.bytes.get(offset..))
+            .ok_or("truncated file")?;
+        let end = tail.iter().position(|&byte| byte == 0).ok_or("unterminated string")?;
+        Ok(String::from_utf8_lossy(&tail[..end]).into_owned())
+    }
+
+    // Entry `index` of the table at `table_offset`, as a reader of its own.
+    fn entry(&self, table_offset: u64, index: u64, entry_size: u64) -> Result<Reader<'_>, String> {
+        let offset = index.checked_mul(entry_size)
+            .and_then(|offset| offset.checked_add(table_offset))
+            .ok_or("truncated file")?;
+        Ok(Reader { bytes: self.slice(offset, entry_size)? })
+    }
+
+    fn section(&self, section_headers_offset: u64, index: u64) -> Result<Section, String> {
+        let header = self.entry(section_headers_offset, index, SECTION_HEADER_SIZE)?;
+        Ok(Section {
+            name: header.u32(0)?,
+            kind: header.u32(4)?,
+            flags: header.u64(8)?,
+            offset: header.u64(24)?,
+            size: header.u64(32)?,
+            link: header.u32(40)?,
+            info: header.u32(44)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object_file() -> Vec<u8> {
+        write_object_file(&ObjectFile {
+            text: vec![0xb8, 2, 0, 0, 0, 0xc3],
+            symbols: vec![Symbol { name: "main".into(), offset: 0, size: 6 }],
+            relocations: Vec::new(),
+        })
+    }
+
+    fn patch(bytes: &[u8], offset: u64, value: &[u8]) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        bytes[offset as usize..offset as usize + value.len()].copy_from_slice(value);
+        bytes
+    }
+
+    // Offsets near `u64::MAX` in the headers are truncated files, not overflows.
+    #[test]
+    fn overflowing_offsets() {
+        let bytes = object_file();
+        assert_eq!(read_object_file(&bytes).unwrap().symbols.len(), 1);
+        for section_headers_offset in [u64::MAX, u64::MAX - SECTION_HEADER_SIZE + 1, u64::MAX / SECTION_HEADER_SIZE] {
+            let bytes = patch(&bytes, 40, &section_headers_offset.to_le_bytes());
+            assert_eq!(read_object_file(&bytes).unwrap_err(), "truncated file");
+        }
+
+        let section_headers_offset = Reader { bytes: &bytes }.u64(40).unwrap();
+        let section_count = Reader { bytes: &bytes }.u16(60).unwrap() as u64;
+        for index in 0..section_count {
+            let header = section_headers_offset + index * SECTION_HEADER_SIZE;
+            for (field, value) in [(0, &u32::MAX.to_le_bytes()[..]), (24, &u64::MAX.to_le_bytes()), (32, &u64::MAX.to_le_bytes())] {
+                // Sections nothing reads may still load.
+                let _ = read_object_file(&patch(&bytes, header + field, value));
+            }
+        }
+    }
+}
@@ -1,59 +1,388 @@
+use std::fmt::Write;
+
 use crate::ast_nodes::*;
+use crate::frame::FrameLayout;
+use crate::debug_info::DebugInfo;
+
+use AsmSyntax::*;
+use ObjectFormat::*;
+use OperandSize::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AsmSyntax {
+    #[default]
+    Att,
+    Intel,
+}
+
+impl TryFrom<&str> for AsmSyntax {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "att" => Ok(Att),
+            "intel" => Ok(Intel),
+            _ => Err(format!("Unsupported assembly syntax `{value}`")),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ObjectFormat {
+    #[default]
+    Elf,
+    MachO,
+}
+
+impl ObjectFormat {
+    fn symbol_prefix(&self) -> &'static str {
+        match self {
+            Elf => "",
+            MachO => "_",
+        }
+    }
+
+    fn text_section(&self) -> Option<&'static str> {
+        match self {
+            Elf => None,
+            MachO => Some(".section __TEXT,__text,regular,pure_instructions"),
+        }
+    }
+
+    // Marks the stack as non-executable for the GNU linker; Mach-O has no such note.
+    fn trailer(&self) -> Option<&'static str> {
+        match self {
+            Elf => Some(".section .note.GNU-stack,\"\",@progbits"),
+            MachO => None,
+        }
+    }
+}
+
+impl TryFrom<&str> for ObjectFormat {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "elf" => Ok(Elf),
+            "mach-o" | "macho" => Ok(MachO),
+            _ => Err(format!("Unsupported object format `{value}`")),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct EmitOptions {
+    syntax: AsmSyntax,
+    object_format: ObjectFormat,
+    symbol_prefix: Option<String>,
+    debug_info: bool,
+}
+
+impl EmitOptions {
+    pub fn set_syntax(&mut self, syntax: AsmSyntax) {
+        self.syntax = syntax;
+    }
+
+    pub fn set_object_format(&mut self, object_format: ObjectFormat) {
+        self.object_format = object_format;
+    }
+
+    /// Overrides the object format's symbol prefix.
+    pub fn set_symbol_prefix(&mut self, symbol_prefix: &str) {
+        self.symbol_prefix = Some(symbol_prefix.into());
+    }
+
+    /// Adds DWARF line tables, call frame information and `.debug_info` for gdb, like `-g`.
+    pub fn enable_debug_info(&mut self) {
+        self.debug_info = true;
+    }
+
+    pub(crate) fn debug_info(&self) -> bool {
+        self.debug_info
+    }
+
+    pub(crate) fn object_format(&self) -> ObjectFormat {
+        self.object_format
+    }
+
+    pub(crate) fn symbol(&self, name: &str) -> String {
+        let prefix = self.symbol_prefix.as_deref().unwrap_or(self.object_format.symbol_prefix());
+        format!("{prefix}{name}")
+    }
+
+    fn syntax(&self) -> &'static dyn Syntax {
+        match self.syntax {
+            Att => &AttSyntax,
+            Intel => &IntelSyntax,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum OperandSize {
+    Longword,
+    Quadword,
+}
 
-pub fn emit_asm_program(asm_program: AsmProgram) -> String {
+/// How instructions and operands are spelled in one assembler dialect.
+trait Syntax {
+    fn prelude(&self) -> Option<&'static str>;
+    fn mnemonic(&self, base: &str, size: OperandSize) -> String;
+    fn register(&self, name: &str) -> String;
+    fn immediate(&self, integer: u32) -> String;
+    fn frame_slot(&self, offset: i32, size: OperandSize) -> String;
+    fn operands(&self, src: &str, dst: &str) -> String;
+}
+
+struct AttSyntax;
+
+impl Syntax for AttSyntax {
+    fn prelude(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn mnemonic(&self, base: &str, size: OperandSize) -> String {
+        match size {
+            Longword => format!("{base}l"),
+            Quadword => format!("{base}q"),
+        }
+    }
+
+    fn register(&self, name: &str) -> String {
+        format!("%{name}")
+    }
+
+    fn immediate(&self, integer: u32) -> String {
+        format!("${integer}")
+    }
+
+    fn frame_slot(&self, offset: i32, _: OperandSize) -> String {
+        format!("{offset}(%rbp)")
+    }
+
+    fn operands(&self, src: &str, dst: &str) -> String {
+        format!("{src}, {dst}")
+    }
+}
+
+struct IntelSyntax;
+
+impl Syntax for IntelSyntax {
+    fn prelude(&self) -> Option<&'static str> {
+        Some(".intel_syntax noprefix")
+    }
+
+    fn mnemonic(&self, base: &str, _: OperandSize) -> String {
+        base.into()
+    }
+
+    fn register(&self, name: &str) -> String {
+        name.into()
+    }
+
+    fn immediate(&self, integer: u32) -> String {
+        format!("{integer}")
+    }
+
+    fn frame_slot(&self, offset: i32, size: OperandSize) -> String {
+        let size = match size {
+            Longword => "DWORD",
+            Quadword => "QWORD",
+        };
+        format!("{size} PTR [rbp{offset:+}]")
+    }
+
+    fn operands(&self, src: &str, dst: &str) -> String {
+        format!("{dst}, {src}")
+    }
+}
+
+pub fn emit_asm_program(asm_program: AsmProgram, options: &EmitOptions) -> String {
     let asm::Program(function_definition) = asm_program;
-    let mut asm_code = emit_asm_function_definition(function_definition);
-    asm_code.push_str("\n\t.section .note.GNU-stack,\"\",@progbits");
+    let mut asm_code = String::new();
+    if let Some(prelude) = options.syntax().prelude() {
+        asm_code.push_str(&format!("\t{prelude}\n"));
+    }
+    if let Some(text_section) = options.object_format.text_section() {
+        asm_code.push_str(&format!("\t{text_section}\n"));
+    }
+    asm_code.push_str(&emit_asm_function_definition(function_definition, options));
+    if let Some(trailer) = options.object_format.trailer() {
+        asm_code.push_str(&format!("\n\t{trailer}"));
+    }
     asm_code
 }
 
-fn emit_asm_function_definition(function_definition: AsmFunctionDefinition) -> String {
-    let asm::Function(asm::Identifier(name), instructions) = function_definition;
+// With debug info, `.file` directives come first, and the function is wrapped in CFI and followed by the DWARF sections.
+fn emit_asm_function_definition(function_definition: AsmFunctionDefinition, options: &EmitOptions) -> String {
+    let mut debug_info = options.debug_info.then(|| DebugInfo::new(&function_definition));
+    let asm::Function(asm::Identifier(name), instructions, frame_layout, span) = &function_definition;
+    let syntax = options.syntax();
+    let symbol = options.symbol(name);
     let mut asm_code = String::new();
-    asm_code.push_str(&format!("\t.globl {name}\n"));
-    asm_code.push_str(&format!("{name}:\n"));
-    asm_code.push_str(&format!("\tpushq\t%rbp\n"));
-    asm_code.push_str(&format!("\tmovq\t%rsp, %rbp\n"));
-    for instruction in emit_asm_instructions(instructions).lines() {
+    push_directives(&mut asm_code, debug_info.as_ref().map(|debug_info| debug_info.start(&symbol)));
+    asm_code.push_str(&format!("\t.globl {symbol}\n"));
+    asm_code.push_str(&format!("{symbol}:\n"));
+    asm_code.push_str(&emit_frame_layout_comment(frame_layout, syntax));
+    push_directives(&mut asm_code, debug_info.as_mut().map(|debug_info| debug_info.prologue(span)));
+    asm_code.push_str(&format!("\t{}\t{}\n", syntax.mnemonic("push", Quadword), syntax.register("rbp")));
+    push_directives(&mut asm_code, debug_info.as_ref().map(DebugInfo::after_push_rbp));
+    asm_code.push_str(&format!("\t{}\t{}\n", syntax.mnemonic("mov", Quadword), syntax.operands(&syntax.register("rsp"), &syntax.register("rbp"))));
+    push_directives(&mut asm_code, debug_info.as_ref().map(DebugInfo::after_set_frame_pointer));
+    for instruction in emit_asm_instructions(instructions.clone(), syntax, debug_info.as_mut()).lines() {
         asm_code.push_str(&format!("\t{instruction}\n"));
     }
+    if let Some(debug_info) = &debug_info {
+        asm_code.push_str(&debug_info.end(&symbol, &function_definition));
+    }
     asm_code
 }
 
-fn emit_asm_instructions(instructions: Vec<AsmInstruction>) -> String {
-    instructions
-        .into_iter()
-        .map(|instruction| {
-            match instruction {
-                asm::Mov(src, dst) => {
-                    let src = emit_asm_operand(src);
-                    let dst = emit_asm_operand(dst);
-                    format!("movl\t{src}, {dst}\n")
-                },
-                asm::Ret => {
-                    let epilogue = "movq\t%rbp, %rsp\npopq\t%rbp";
-                    format!("{epilogue}\nret\n")
-                },
-                asm::Unary(operator, operand) => {
-                    let operator = match operator {
-                        asm::Neg => "negl",
-                        asm::Not => "notl",
-                    };
-                    let operand = emit_asm_operand(operand);
-                    format!("{operator}\t{operand}\n")
-                },
-                asm::AllocateStack(integer) => format!("subq\t${}, %rsp\n", integer * 4),
-            }
-        })
+fn push_directives(asm_code: &mut String, directives: Option<Vec<String>>) {
+    for directive in directives.into_iter().flatten() {
+        asm_code.push_str(&format!("\t{directive}\n"));
+    }
+}
+
+fn emit_frame_layout_comment(frame_layout: &FrameLayout, syntax: &dyn Syntax) -> String {
+    frame_layout.slots()
+        .iter()
+        .map(|slot| format!("\t# {}: {}, {} bytes\n", syntax.frame_slot(slot.offset, Longword), slot.name, slot.size))
         .collect()
 }
 
-fn emit_asm_operand(operand: AsmOperand) -> String {
+fn emit_asm_instructions(instructions: Vec<AsmInstruction>, syntax: &dyn Syntax, mut debug_info: Option<&mut DebugInfo>) -> String {
+    let mut asm_code = String::new();
+    for instruction in instructions {
+        if let Some(loc) = debug_info.as_mut().and_then(|debug_info| debug_info.loc(&instruction)) {
+            writeln!(asm_code, "{loc}").unwrap();
+        }
+        match instruction {
+            asm::Mov(src, dst, _) => {
+                let src = emit_asm_operand(src, syntax);
+                let dst = emit_asm_operand(dst, syntax);
+                writeln!(asm_code, "{}\t{}", syntax.mnemonic("mov", Longword), syntax.operands(&src, &dst)).unwrap();
+            },
+            asm::Ret(_) => {
+                let rbp = syntax.register("rbp");
+                let epilogue = debug_info.as_ref().map(|debug_info| debug_info.epilogue());
+                if let Some((before, _)) = &epilogue {
+                    writeln!(asm_code, "{before}").unwrap();
+                }
+                writeln!(asm_code, "{}\t{}", syntax.mnemonic("mov", Quadword), syntax.operands(&rbp, &syntax.register("rsp"))).unwrap();
+                writeln!(asm_code, "{}\t{rbp}", syntax.mnemonic("pop", Quadword)).unwrap();
+                if let Some((_, after)) = &epilogue {
+                    writeln!(asm_code, "{after}").unwrap();
+                }
+                writeln!(asm_code, "ret").unwrap();
+                if let Some(debug_info) = &debug_info {
+                    writeln!(asm_code, "{}", debug_info.after_ret()).unwrap();
+                }
+            },
+            asm::Unary(operator, operand, _) => {
+                let operator = match operator {
+                    asm::Neg => "neg",
+                    asm::Not => "not",
+                };
+                let operand = emit_asm_operand(operand, syntax);
+                writeln!(asm_code, "{}\t{operand}", syntax.mnemonic(operator, Longword)).unwrap();
+            },
+            asm::Binary(operator, src, dst, _) => {
+                let operator = match operator {
+                    asm::Xor => "xor",
+                };
+                let src = emit_asm_operand(src, syntax);
+                let dst = emit_asm_operand(dst, syntax);
+                writeln!(asm_code, "{}\t{}", syntax.mnemonic(operator, Longword), syntax.operands(&src, &dst)).unwrap();
+            },
+            asm::AllocateStack(integer, _) => {
+                let operands = syntax.operands(&syntax.immediate(integer), &syntax.register("rsp"));
+                writeln!(asm_code, "{}\t{operands}", syntax.mnemonic("sub", Quadword)).unwrap();
+                if let Some(debug_info) = debug_info.as_mut() {
+                    debug_info.after_allocate_stack(integer);
+                }
+            },
+            asm::Push(reg, _) => {
+                writeln!(asm_code, "{}\t{}", syntax.mnemonic("push", Quadword), syntax.register(emit_asm_register_quadword(reg))).unwrap();
+                if let Some(debug_info) = debug_info.as_mut() {
+                    writeln!(asm_code, "{}", debug_info.after_push(reg)).unwrap();
+                }
+            },
+            asm::Pop(reg, _) => {
+                writeln!(asm_code, "{}\t{}", syntax.mnemonic("pop", Quadword), syntax.register(emit_asm_register_quadword(reg))).unwrap();
+            },
+        }
+    }
+    asm_code
+}
+
+fn emit_asm_operand(operand: AsmOperand, syntax: &dyn Syntax) -> String {
     match operand {
-        asm::Register(asm::AX) => "%eax".into(),
-        asm::Register(asm::R10) => "%r10d".into(),
-        asm::Stack(integer) => format!("-{}(%rbp)", (integer + 1) * 4),
-        asm::Imm(integer) => format!("${integer}"),
+        asm::Register(reg) => syntax.register(emit_asm_register_longword(reg)),
+        asm::Stack(offset) => syntax.frame_slot(offset, Longword),
+        asm::Imm(integer) => syntax.immediate(integer),
         _ => panic!("Unsupported asm operand"),
     }
 }
+
+fn emit_asm_register_longword(reg: AsmReg) -> &'static str {
+    match reg {
+        asm::AX => "eax",
+        asm::BX => "ebx",
+        asm::CX => "ecx",
+        asm::DX => "edx",
+        asm::SI => "esi",
+        asm::DI => "edi",
+        asm::R8 => "r8d",
+        asm::R9 => "r9d",
+        asm::R10 => "r10d",
+        asm::R11 => "r11d",
+        asm::R12 => "r12d",
+        asm::R13 => "r13d",
+        asm::R14 => "r14d",
+        asm::R15 => "r15d",
+    }
+}
+
+fn emit_asm_register_quadword(reg: AsmReg) -> &'static str {
+    match reg {
+        asm::AX => "rax",
+        asm::BX => "rbx",
+        asm::CX => "rcx",
+        asm::DX => "rdx",
+        asm::SI => "rsi",
+        asm::DI => "rdi",
+        asm::R8 => "r8",
+        asm::R9 => "r9",
+        asm::R10 => "r10",
+        asm::R11 => "r11",
+        asm::R12 => "r12",
+        asm::R13 => "r13",
+        asm::R14 => "r14",
+        asm::R15 => "r15",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+
+    #[test]
+    fn slots_are_listed_ahead_of_the_prologue() {
+        let expected = [
+            (Att, "\t# -4(%rbp): t0, 4 bytes\n\t# -8(%rbp): t1, 4 bytes\n\tpushq\t%rbp\n"),
+            (Intel, "\t# DWORD PTR [rbp-4]: t0, 4 bytes\n\t# DWORD PTR [rbp-8]: t1, 4 bytes\n\tpush\trbp\n"),
+        ];
+        for (syntax, comments) in expected {
+            let mut frame_layout = FrameLayout::default();
+            frame_layout.allocate("t0", 4, 4);
+            frame_layout.allocate("t1", 4, 4);
+            let function = asm::Function(asm::Identifier("main".to_string()), vec![asm::Ret(Span::default())], frame_layout, Span::default());
+            let mut options = EmitOptions::default();
+            options.set_syntax(syntax);
+            let asm_code = emit_asm_program(asm::Program(function), &options);
+            assert!(asm_code.contains(&format!("main:\n{comments}")), "{asm_code}");
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::ast_nodes::*;
+
+const MAX_ADD_SUB_IMMEDIATE: u32 = 4095;
+
+pub fn emit_aarch64_program(aarch64_program: Aarch64Program) -> String {
+    let aarch64::Program(function_definition) = aarch64_program;
+    let mut asm_code = emit_aarch64_function_definition(function_definition);
+    asm_code.push_str("\n\t.section .note.GNU-stack,\"\",%progbits");
+    asm_code
+}
+
+fn emit_aarch64_function_definition(function_definition: Aarch64FunctionDefinition) -> String {
+    let aarch64::Function(aarch64::Identifier(name), instructions, _) = function_definition;
+    let mut asm_code = String::new();
+    asm_code.push_str(&format!("\t.globl {name}\n"));
+    asm_code.push_str(&format!("\t.type {name}, %function\n"));
+    asm_code.push_str(&format!("{name}:\n"));
+    asm_code.push_str("\tstp\tx29, x30, [sp, #-16]!\n");
+    asm_code.push_str("\tmov\tx29, sp\n");
+    for instruction in emit_aarch64_instructions(instructions).lines() {
+        asm_code.push_str(&format!("\t{instruction}\n"));
+    }
+    asm_code
+}
+
+fn emit_aarch64_instructions(instructions: Vec<Aarch64Instruction>) -> String {
+    instructions
+        .into_iter()
+        .map(|instruction| {
+            match instruction {
+                aarch64::Mov(src, dst) => {
+                    let src = emit_aarch64_operand(src);
+                    let dst = emit_aarch64_operand(dst);
+                    format!("mov\t{dst}, {src}\n")
+                },
+                aarch64::Unary(operator, src, dst) => {
+                    let operator = match operator {
+                        aarch64::Neg => "neg",
+                        aarch64::Mvn => "mvn",
+                    };
+                    let src = emit_aarch64_operand(src);
+                    let dst = emit_aarch64_operand(dst);
+                    format!("{operator}\t{dst}, {src}\n")
+                },
+                aarch64::Movz(reg, integer, 0) => format!("movz\t{}, #{integer}\n", emit_aarch64_register(reg)),
+                aarch64::Movz(reg, integer, shift) => format!("movz\t{}, #{integer}, lsl #{shift}\n", emit_aarch64_register(reg)),
+                aarch64::Movk(reg, integer, shift) => format!("movk\t{}, #{integer}, lsl #{shift}\n", emit_aarch64_register(reg)),
+                aarch64::Ldr(reg, offset) => emit_aarch64_frame_access("ldr", "ldur", reg, offset),
+                aarch64::Str(reg, offset) => emit_aarch64_frame_access("str", "stur", reg, offset),
+                aarch64::AllocateStack(integer) if integer <= MAX_ADD_SUB_IMMEDIATE => format!("sub\tsp, sp, #{integer}\n"),
+                aarch64::AllocateStack(integer) => format!("{}sub\tsp, sp, x16\n", emit_aarch64_load_x16(integer)),
+                aarch64::Ret => {
+                    let epilogue = "mov\tsp, x29\nldp\tx29, x30, [sp], #16";
+                    format!("{epilogue}\nret\n")
+                },
+            }
+        })
+        .collect()
+}
+
+// Frame slots live below the frame pointer: small offsets use the unscaled `ldur`/`stur` forms, larger ones go
+// through the intra-procedure-call scratch register `x16`.
+fn emit_aarch64_frame_access(mnemonic: &str, unscaled: &str, reg: Aarch64Reg, offset: i32) -> String {
+    let reg = emit_aarch64_register(reg);
+    if (-256..256).contains(&offset) {
+        return format!("{unscaled}\t{reg}, [x29, #{offset}]\n");
+    }
+    let distance = offset.unsigned_abs();
+    if distance <= MAX_ADD_SUB_IMMEDIATE {
+        return format!("sub\tx16, x29, #{distance}\n{mnemonic}\t{reg}, [x16]\n");
+    }
+    format!("{}sub\tx16, x29, x16\n{mnemonic}\t{reg}, [x16]\n", emit_aarch64_load_x16(distance))
+}
+
+// `add`/`sub` only encode 12-bit immediates, so larger ones are materialized in `x16` with `movz`/`movk` first.
+fn emit_aarch64_load_x16(integer: u32) -> String {
+    let (low, high) = (integer & 0xffff, integer >> 16);
+    match (low, high) {
+        (_, 0) => format!("movz\tx16, #{low}\n"),
+        (0, _) => format!("movz\tx16, #{high}, lsl #16\n"),
+        _ => format!("movz\tx16, #{low}\nmovk\tx16, #{high}, lsl #16\n"),
+    }
+}
+
+fn emit_aarch64_operand(operand: Aarch64Operand) -> String {
+    match operand {
+        aarch64::Register(reg) => emit_aarch64_register(reg).into(),
+        aarch64::Imm(integer) => format!("#{integer}"),
+        _ => panic!("Unsupported aarch64 operand"),
+    }
+}
+
+fn emit_aarch64_register(reg: Aarch64Reg) -> &'static str {
+    match reg {
+        aarch64::W0 => "w0",
+        aarch64::W9 => "w9",
+    }
+}
@@ -0,0 +1,104 @@
+//! # LLVM IR emission
+//!
+//! Translates TACKY into textual LLVM IR. Every TACKY variable gets an `alloca` in the entry block and is accessed
+//! through `load`/`store`, which keeps the output in SSA form without computing phi nodes: LLVM's `mem2reg` pass
+//! promotes them to registers. Pointers use the opaque `ptr` type.
+//!
+//! The `alloca` of a variable is named `%var.<name>`, apart from the numbered `%v<n>` values, so that no TACKY name can
+//! collide with them.
+
+use crate::ast_nodes::*;
+
+pub fn emit_llvm_module(tacky_program: TackyProgram, source_filename: &str) -> String {
+    let tacky::Program(function_definition) = tacky_program;
+    let mut llvm_code = String::new();
+    let source_filename = string_constant(source_filename);
+    llvm_code.push_str(&format!("; ModuleID = {source_filename}\n"));
+    llvm_code.push_str(&format!("source_filename = {source_filename}\n\n"));
+    llvm_code.push_str(&emit_llvm_function(function_definition));
+    llvm_code
+}
+
+// LLVM string constants escape `"`, `\` and non-printable bytes as `\XX` in hex.
+fn string_constant(string: &str) -> String {
+    let mut constant = String::from('"');
+    for byte in string.bytes() {
+        match byte {
+            b'"' | b'\\' | 0..=0x1f | 0x7f..=0xff => constant.push_str(&format!("\\{byte:02X}")),
+            _ => constant.push(byte as char),
+        }
+    }
+    constant.push('"');
+    constant
+}
+
+#[derive(Default)]
+struct LlvmFunctionBuilder {
+    allocas: Vec<String>,
+    body: Vec<String>,
+    next_value: usize,
+}
+
+impl LlvmFunctionBuilder {
+    fn new_value(&mut self) -> String {
+        let value = format!("%v{}", self.next_value);
+        self.next_value += 1;
+        value
+    }
+
+    fn variable(&mut self, name: &str) -> String {
+        let address = format!("%var.{name}");
+        if !self.allocas.contains(&address) {
+            self.allocas.push(address.clone());
+        }
+        address
+    }
+
+    fn operand(&mut self, operand: TackyOperand) -> String {
+        match operand {
+            tacky::Constant(integer) => format!("{}", integer as i32),
+            tacky::Variable(tacky::Identifier(name)) => {
+                let address = self.variable(&name);
+                let value = self.new_value();
+                self.body.push(format!("{value} = load i32, ptr {address}, align 4"));
+                value
+            },
+        }
+    }
+}
+
+fn emit_llvm_function(function_definition: TackyFunctionDefinition) -> String {
+    let tacky::Function(tacky::Identifier(name), instructions, _) = function_definition;
+    let mut builder = LlvmFunctionBuilder::default();
+    for instruction in instructions {
+        match instruction {
+            tacky::Return(val, _) => {
+                let val = builder.operand(val);
+                builder.body.push(format!("ret i32 {val}"));
+            },
+            TackyInstruction::Unary(operator, src, dst, _) => {
+                let src = builder.operand(src);
+                let result = builder.new_value();
+                builder.body.push(match operator {
+                    tacky::Negate => format!("{result} = sub i32 0, {src}"),
+                    tacky::Complement => format!("{result} = xor i32 {src}, -1"),
+                });
+                let tacky::Variable(tacky::Identifier(dst)) = dst else {
+                    unreachable!("Unary destination should be a variable");
+                };
+                let address = builder.variable(&dst);
+                builder.body.push(format!("store i32 {result}, ptr {address}, align 4"));
+            },
+        }
+    }
+
+    let mut llvm_code = format!("define i32 @{name}() {{\nentry:\n");
+    for address in &builder.allocas {
+        llvm_code.push_str(&format!("  {address} = alloca i32, align 4\n"));
+    }
+    for line in &builder.body {
+        llvm_code.push_str(&format!("  {line}\n"));
+    }
+    llvm_code.push_str("}\n");
+    llvm_code
+}
@@ -0,0 +1,90 @@
+use crate::ast_nodes::*;
+
+pub fn emit_riscv64_program(riscv64_program: Riscv64Program) -> String {
+    let riscv64::Program(function_definition) = riscv64_program;
+    let mut asm_code = emit_riscv64_function_definition(function_definition);
+    asm_code.push_str("\n\t.section .note.GNU-stack,\"\",@progbits");
+    asm_code
+}
+
+fn emit_riscv64_function_definition(function_definition: Riscv64FunctionDefinition) -> String {
+    let riscv64::Function(riscv64::Identifier(name), instructions, _) = function_definition;
+    let mut asm_code = String::new();
+    asm_code.push_str(&format!("\t.globl {name}\n"));
+    asm_code.push_str(&format!("\t.type {name}, @function\n"));
+    asm_code.push_str(&format!("{name}:\n"));
+    asm_code.push_str("\taddi\tsp, sp, -16\n");
+    asm_code.push_str("\tsd\tra, 8(sp)\n");
+    asm_code.push_str("\tsd\ts0, 0(sp)\n");
+    asm_code.push_str("\taddi\ts0, sp, 16\n");
+    for instruction in emit_riscv64_instructions(instructions).lines() {
+        asm_code.push_str(&format!("\t{instruction}\n"));
+    }
+    asm_code
+}
+
+fn emit_riscv64_instructions(instructions: Vec<Riscv64Instruction>) -> String {
+    instructions
+        .into_iter()
+        .map(|instruction| {
+            match instruction {
+                riscv64::Mv(src, dst) => {
+                    let src = emit_riscv64_operand(src);
+                    let dst = emit_riscv64_operand(dst);
+                    format!("mv\t{dst}, {src}\n")
+                },
+                riscv64::Unary(operator, src, dst) => {
+                    let operator = match operator {
+                        riscv64::Neg => "negw",
+                        riscv64::Not => "not",
+                    };
+                    let src = emit_riscv64_operand(src);
+                    let dst = emit_riscv64_operand(dst);
+                    format!("{operator}\t{dst}, {src}\n")
+                },
+                riscv64::Lui(reg, integer) => format!("lui\t{}, {integer}\n", emit_riscv64_register(reg)),
+                riscv64::Addi(dst, src, integer) => {
+                    format!("addi\t{}, {}, {integer}\n", emit_riscv64_register(dst), emit_riscv64_register(src))
+                },
+                riscv64::Addiw(dst, src, integer) => {
+                    format!("addiw\t{}, {}, {integer}\n", emit_riscv64_register(dst), emit_riscv64_register(src))
+                },
+                riscv64::Lw(reg, offset) => emit_riscv64_frame_access("lw", reg, offset),
+                riscv64::Sw(reg, offset) => emit_riscv64_frame_access("sw", reg, offset),
+                riscv64::AllocateStack(0) => String::new(),
+                riscv64::AllocateStack(integer) if integer < 2048 => format!("addi\tsp, sp, -{integer}\n"),
+                riscv64::AllocateStack(integer) => format!("li\tt1, {integer}\nsub\tsp, sp, t1\n"),
+                riscv64::Ret => {
+                    let epilogue = "addi\tsp, s0, -16\nld\tra, 8(sp)\nld\ts0, 0(sp)\naddi\tsp, sp, 16";
+                    format!("{epilogue}\nret\n")
+                },
+            }
+        })
+        .collect()
+}
+
+// Loads and stores take a signed 12-bit offset; slots further away from the frame pointer are addressed through the
+// second scratch register `t1`.
+fn emit_riscv64_frame_access(mnemonic: &str, reg: Riscv64Reg, offset: i32) -> String {
+    let reg = emit_riscv64_register(reg);
+    if (-2048..2048).contains(&offset) {
+        return format!("{mnemonic}\t{reg}, {offset}(s0)\n");
+    }
+    format!("li\tt1, {offset}\nadd\tt1, s0, t1\n{mnemonic}\t{reg}, 0(t1)\n")
+}
+
+fn emit_riscv64_operand(operand: Riscv64Operand) -> String {
+    match operand {
+        riscv64::Register(reg) => emit_riscv64_register(reg).into(),
+        riscv64::Imm(integer) => format!("{integer}"),
+        _ => panic!("Unsupported riscv64 operand"),
+    }
+}
+
+fn emit_riscv64_register(reg: Riscv64Reg) -> &'static str {
+    match reg {
+        riscv64::Zero => "zero",
+        riscv64::A0 => "a0",
+        riscv64::T0 => "t0",
+    }
+}
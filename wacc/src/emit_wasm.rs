@@ -0,0 +1,45 @@
+use crate::ast_nodes::*;
+use crate::codegen_wasm::STACK_POINTER;
+
+// One 64 KiB page, all of it shadow stack.
+const MEMORY_PAGES: u32 = 1;
+
+pub fn emit_wasm_module(wasm_module: WasmModule) -> String {
+    let wasm::Module(function) = wasm_module;
+    let mut wat_code = String::from("(module\n");
+    wat_code.push_str(&format!("  (memory (export \"memory\") {MEMORY_PAGES})\n"));
+    wat_code.push_str(&format!("  (global ${STACK_POINTER} (mut i32) (i32.const {}))\n", MEMORY_PAGES * 65536));
+    wat_code.push_str(&emit_wasm_function(function));
+    wat_code.push(')');
+    wat_code
+}
+
+fn emit_wasm_function(function: WasmFunction) -> String {
+    let wasm::Function(wasm::Identifier(name), locals, instructions, frame_layout) = function;
+    let mut wat_code = String::new();
+    wat_code.push_str(&format!("  (func ${name} (export \"{name}\") (result i32)\n"));
+    for wasm::Identifier(local) in locals {
+        wat_code.push_str(&format!("    (local ${local} i32)\n"));
+    }
+    for slot in frame_layout.slots() {
+        wat_code.push_str(&format!("    ;; {}($fp): {}, {} bytes\n", frame_layout.size() as i32 + slot.offset, slot.name, slot.size));
+    }
+    for instruction in instructions {
+        let instruction = match instruction {
+            wasm::I32Const(integer) => format!("i32.const {integer}"),
+            wasm::I32Add => "i32.add".into(),
+            wasm::I32Sub => "i32.sub".into(),
+            wasm::I32Xor => "i32.xor".into(),
+            wasm::I32Load(offset) => format!("i32.load offset={offset}"),
+            wasm::I32Store(offset) => format!("i32.store offset={offset}"),
+            wasm::LocalGet(wasm::Identifier(local)) => format!("local.get ${local}"),
+            wasm::LocalTee(wasm::Identifier(local)) => format!("local.tee ${local}"),
+            wasm::GlobalGet(wasm::Identifier(global)) => format!("global.get ${global}"),
+            wasm::GlobalSet(wasm::Identifier(global)) => format!("global.set ${global}"),
+            wasm::Return => "return".into(),
+        };
+        wat_code.push_str(&format!("    {instruction}\n"));
+    }
+    wat_code.push_str("  )\n");
+    wat_code
+}
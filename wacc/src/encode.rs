@@ -0,0 +1,157 @@
+//! # x86-64 machine code
+//!
+//! Encodes an `AsmProgram` into the bytes of its `.text` section, the same instructions `emit` prints:
+//! - Operands are 32-bit, except for the frame setup on `%rsp` and `%rbp`, and for `push`/`pop`;
+//! - Stack slots are addressed from `%rbp`, with an 8-bit displacement when it fits and a 32-bit one otherwise;
+//! - Immediates always take 32 bits, so encodings can be longer than gas's, but they disassemble to the same
+//!   instructions.
+
+use crate::ast_nodes::*;
+use crate::elf::{ObjectFile, Symbol};
+use crate::emit::EmitOptions;
+
+const RBP: u8 = 5;
+const RSP: u8 = 4;
+
+pub fn encode_asm_program(asm_program: &AsmProgram, options: &EmitOptions) -> ObjectFile {
+    let asm::Program(asm::Function(asm::Identifier(name), instructions, ..)) = asm_program;
+    let mut code = Code::default();
+    code.push(RBP);
+    code.rex_rm(true, RSP, RegisterOrMemory::Register(RBP), &[0x89]);
+    for instruction in instructions {
+        code.instruction(instruction);
+    }
+    let symbol = Symbol { name: options.symbol(name), offset: 0, size: code.bytes.len() as u64 };
+    ObjectFile { text: code.bytes, symbols: vec![symbol], relocations: Vec::new() }
+}
+
+fn register_number(reg: AsmReg) -> u8 {
+    match reg {
+        asm::AX => 0,
+        asm::CX => 1,
+        asm::DX => 2,
+        asm::BX => 3,
+        asm::SI => 6,
+        asm::DI => 7,
+        asm::R8 => 8,
+        asm::R9 => 9,
+        asm::R10 => 10,
+        asm::R11 => 11,
+        asm::R12 => 12,
+        asm::R13 => 13,
+        asm::R14 => 14,
+        asm::R15 => 15,
+    }
+}
+
+// The operand in the ModR/M byte's r/m field.
+enum RegisterOrMemory {
+    Register(u8),
+    Frame(i32),
+}
+
+impl From<&AsmOperand> for RegisterOrMemory {
+    fn from(operand: &AsmOperand) -> Self {
+        match operand {
+            asm::Register(reg) => Self::Register(register_number(*reg)),
+            asm::Stack(offset) => Self::Frame(*offset),
+            _ => panic!("Unsupported asm operand `{operand}` for r/m"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Code {
+    bytes: Vec<u8>,
+}
+
+impl Code {
+    fn push(&mut self, reg: u8) {
+        if reg >= 8 {
+            self.bytes.push(0x41);
+        }
+        self.bytes.push(0x50 + (reg & 7));
+    }
+
+    fn pop(&mut self, reg: u8) {
+        if reg >= 8 {
+            self.bytes.push(0x41);
+        }
+        self.bytes.push(0x58 + (reg & 7));
+    }
+
+    fn imm32(&mut self, integer: u32) {
+        self.bytes.extend(integer.to_le_bytes());
+    }
+
+    // Writes the REX prefix if needed, then `opcode`, then ModR/M and any displacement, with `reg` in the reg field.
+    fn rex_rm(&mut self, wide: bool, reg: u8, rm: impl Into<RegisterOrMemory>, opcode: &[u8]) {
+        let rm = rm.into();
+        let rm_number = match rm {
+            RegisterOrMemory::Register(number) => number,
+            RegisterOrMemory::Frame(_) => RBP,
+        };
+        let rex = 0x40 | (wide as u8) << 3 | (reg >> 3) << 2 | rm_number >> 3;
+        if rex != 0x40 {
+            self.bytes.push(rex);
+        }
+        self.bytes.extend(opcode);
+        match rm {
+            RegisterOrMemory::Register(number) => self.bytes.push(0xc0 | (reg & 7) << 3 | (number & 7)),
+            RegisterOrMemory::Frame(offset) => match i8::try_from(offset) {
+                Ok(offset) => {
+                    self.bytes.push(0x40 | (reg & 7) << 3 | RBP);
+                    self.bytes.push(offset as u8);
+                },
+                Err(_) => {
+                    self.bytes.push(0x80 | (reg & 7) << 3 | RBP);
+                    self.bytes.extend(offset.to_le_bytes());
+                },
+            },
+        }
+    }
+
+    fn instruction(&mut self, instruction: &AsmInstruction) {
+        match instruction {
+            asm::Mov(asm::Imm(integer), asm::Register(reg), _) => {
+                let reg = register_number(*reg);
+                if reg >= 8 {
+                    self.bytes.push(0x41);
+                }
+                self.bytes.push(0xb8 + (reg & 7));
+                self.imm32(*integer);
+            },
+            asm::Mov(asm::Imm(integer), dst, _) => {
+                self.rex_rm(false, 0, dst, &[0xc7]);
+                self.imm32(*integer);
+            },
+            asm::Mov(asm::Register(src), dst, _) => self.rex_rm(false, register_number(*src), dst, &[0x89]),
+            asm::Mov(src, asm::Register(dst), _) => self.rex_rm(false, register_number(*dst), src, &[0x8b]),
+            asm::Unary(operator, operand, _) => {
+                let extension = match operator {
+                    asm::Not => 2,
+                    asm::Neg => 3,
+                };
+                self.rex_rm(false, extension, operand, &[0xf7]);
+            },
+            asm::Binary(asm::Xor, asm::Imm(integer), dst, _) => {
+                self.rex_rm(false, 6, dst, &[0x81]);
+                self.imm32(*integer);
+            },
+            asm::Binary(asm::Xor, asm::Register(src), dst, _) => self.rex_rm(false, register_number(*src), dst, &[0x31]),
+            asm::Binary(asm::Xor, src, asm::Register(dst), _) => self.rex_rm(false, register_number(*dst), src, &[0x33]),
+            asm::AllocateStack(size, _) => {
+                self.rex_rm(true, 5, RegisterOrMemory::Register(RSP), &[0x81]);
+                self.imm32(*size);
+            },
+            asm::Push(reg, _) => self.push(register_number(*reg)),
+            asm::Pop(reg, _) => self.pop(register_number(*reg)),
+            asm::Ret(_) => {
+                self.rex_rm(true, RBP, RegisterOrMemory::Register(RSP), &[0x89]);
+                self.pop(RBP);
+                self.bytes.push(0xc3);
+            },
+            _ => panic!("Unsupported asm instruction `{instruction}`"),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! # Stack frame layout
+//!
+//! Assigns every stack-resident object an offset from the frame pointer according to its size and alignment, and
+//! rounds the frame so that the stack pointer stays 16-byte aligned once the callee-saved registers are pushed.
+//! The layout is recorded on the function definition so that the emitter and the debug dumps agree on it.
+
+use std::fmt;
+
+const STACK_ALIGNMENT: u32 = 16;
+
+#[derive(Debug, Clone)]
+pub struct FrameSlot {
+    pub name: String,
+    pub size: u32,
+    pub alignment: u32,
+    pub offset: i32,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FrameLayout {
+    slots: Vec<FrameSlot>,
+    used: u32,
+    size: u32,
+}
+
+impl FrameLayout {
+    /// Returns the offset from the frame pointer of the slot for `name`, allocating it on first use.
+    pub fn allocate(&mut self, name: &str, size: u32, alignment: u32) -> i32 {
+        if let Some(offset) = self.offset_of(name) {
+            return offset;
+        }
+        self.used = (self.used + size).next_multiple_of(alignment);
+        let offset = -(self.used as i32);
+        self.slots.push(FrameSlot { name: name.into(), size, alignment, offset });
+        offset
+    }
+
+    pub fn offset_of(&self, name: &str) -> Option<i32> {
+        self.slots.iter().find(|slot| slot.name == name).map(|slot| slot.offset)
+    }
+
+    /// Fixes the frame size, given the number of bytes pushed right after the frame is allocated.
+    pub fn finish(&mut self, pushed_bytes: u32) {
+        self.size = (self.used + pushed_bytes).next_multiple_of(STACK_ALIGNMENT) - pushed_bytes;
+    }
+
+    pub fn slots(&self) -> &[FrameSlot] {
+        &self.slots
+    }
+
+    /// Number of bytes to subtract from the stack pointer in the prologue.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+impl fmt::Display for FrameLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "frame size: {} bytes", self.size)?;
+        for slot in &self.slots {
+            writeln!(f, "  {:>5}: {} ({} bytes, align {})", slot.offset, slot.name, slot.size, slot.alignment)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_aligned() {
+        let mut frame_layout = FrameLayout::default();
+        assert_eq!(frame_layout.allocate("a", 4, 4), -4);
+        // 12 bytes are used once `b` is in, rounded up to its alignment.
+        assert_eq!(frame_layout.allocate("b", 8, 8), -16);
+        assert_eq!(frame_layout.allocate("c", 1, 1), -17);
+        assert_eq!(frame_layout.allocate("d", 4, 4), -24);
+        // A slot is allocated once.
+        assert_eq!(frame_layout.allocate("a", 4, 4), -4);
+        assert_eq!(frame_layout.offset_of("b"), Some(-16));
+        assert_eq!(frame_layout.offset_of("e"), None);
+        assert_eq!(frame_layout.slots().len(), 4);
+    }
+
+    // With the return address and the saved `%rbp`, the frame and the pushes must add up to a multiple of 16 bytes.
+    #[test]
+    fn frame_is_rounded_around_pushes() {
+        let mut frame_layout = FrameLayout::default();
+        frame_layout.allocate("a", 4, 4);
+        frame_layout.allocate("b", 4, 4);
+        frame_layout.allocate("c", 4, 4);
+        for (pushed_registers, size) in [(0, 16), (1, 24), (2, 16), (3, 24), (4, 16), (5, 24)] {
+            frame_layout.finish(8 * pushed_registers);
+            assert_eq!(frame_layout.size(), size, "{pushed_registers} registers pushed");
+        }
+
+        let mut empty = FrameLayout::default();
+        empty.finish(0);
+        assert_eq!(empty.size(), 0);
+        empty.finish(8 * 5);
+        assert_eq!(empty.size(), 8);
+    }
+}
@@ -0,0 +1,168 @@
+//! # TACKY interpreter
+//!
+//! Executes a `TackyProgram` directly, so programs can run without an assembler and serve as a differential oracle
+//! against native execution.
+//! - Every call gets a `Frame` holding its variables. Static data lives in a flat byte `memory` that pointers index
+//!   into; it stays empty until the front end emits string constants;
+//! - `int` arithmetic wraps like the two's complement instructions the backends generate;
+//! - Functions the program doesn't define are looked up among the host-implemented libc functions: `putchar`, and
+//!   `printf` with the `%d`, `%i`, `%u`, `%x`, `%c` and `%%` conversions, its format string read from `memory`.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::ast_nodes::*;
+
+#[derive(Default)]
+struct Frame {
+    variables: HashMap<String, i32>,
+}
+
+impl Frame {
+    fn read(&self, operand: &TackyOperand) -> Result<i32, String> {
+        match operand {
+            tacky::Constant(integer) => Ok(*integer as i32),
+            tacky::Variable(tacky::Identifier(name)) => self.variables.get(name).copied()
+                .ok_or(format!("Read of uninitialized variable `{name}`")),
+        }
+    }
+
+    fn write(&mut self, operand: &TackyOperand, value: i32) {
+        let tacky::Variable(tacky::Identifier(name)) = operand else {
+            unreachable!("Destination should be a variable");
+        };
+        self.variables.insert(name.clone(), value);
+    }
+}
+
+struct Interpreter<'a> {
+    functions: HashMap<&'a str, &'a TackyFunctionDefinition>,
+    call_stack: Vec<Frame>,
+    memory: Vec<u8>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(tacky_program: &'a TackyProgram) -> Self {
+        let tacky::Program(function_definition) = tacky_program;
+        let tacky::Function(tacky::Identifier(name), ..) = function_definition;
+        Self {
+            functions: HashMap::from([(name.as_str(), function_definition)]),
+            call_stack: Vec::new(),
+            memory: Vec::new(),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[i32]) -> Result<i32, String> {
+        let Some(function_definition) = self.functions.get(name).copied() else {
+            return call_host_function(name, args, &self.memory, &mut std::io::stdout().lock());
+        };
+        let tacky::Function(_, instructions, _) = function_definition;
+        self.call_stack.push(Frame::default());
+        let result = self.execute(instructions);
+        self.call_stack.pop();
+        result
+    }
+
+    fn execute(&mut self, instructions: &[TackyInstruction]) -> Result<i32, String> {
+        let frame = self.call_stack.last_mut().expect("That a frame should be pushed before executing");
+        for instruction in instructions {
+            match instruction {
+                tacky::Return(val, _) => {
+                    return frame.read(val);
+                },
+                TackyInstruction::Unary(operator, src, dst, _) => {
+                    let src = frame.read(src)?;
+                    let value = match operator {
+                        tacky::Complement => !src,
+                        tacky::Negate => src.wrapping_neg(),
+                    };
+                    frame.write(dst, value);
+                },
+            }
+        }
+        Err("Function ended without returning".into())
+    }
+}
+
+fn call_host_function(name: &str, args: &[i32], memory: &[u8], out: &mut dyn Write) -> Result<i32, String> {
+    let written = match (name, args) {
+        ("putchar", &[ch]) => vec![ch as u8],
+        ("printf", &[format, ref args @ ..]) => printf(read_c_string(memory, format)?, args)?,
+        _ => return Err(format!("Call to undefined function `{name}` with {} arguments", args.len())),
+    };
+    out.write_all(&written).map_err(|e| format!("Failed to write to stdout: {e}"))?;
+    Ok(match name {
+        "putchar" => written[0] as i32,
+        _ => written.len() as i32,
+    })
+}
+
+fn read_c_string(memory: &[u8], address: i32) -> Result<&[u8], String> {
+    let string = usize::try_from(address).ok().and_then(|address| memory.get(address..))
+        .ok_or(format!("Read of invalid address {address}"))?;
+    let len = string.iter().position(|&byte| byte == 0)
+        .ok_or(format!("String at address {address} isn't NUL-terminated"))?;
+    Ok(&string[..len])
+}
+
+fn printf(format: &[u8], args: &[i32]) -> Result<Vec<u8>, String> {
+    let mut written = Vec::new();
+    let mut args = args.iter();
+    let mut format = format.iter();
+    while let Some(&byte) = format.next() {
+        if byte != b'%' {
+            written.push(byte);
+            continue;
+        }
+        let conversion = *format.next().ok_or("`printf` format ends with `%`")?;
+        if conversion == b'%' {
+            written.push(b'%');
+            continue;
+        }
+        let &arg = args.next().ok_or("`printf` has fewer arguments than conversions")?;
+        match conversion {
+            b'd' | b'i' => written.extend(arg.to_string().bytes()),
+            b'u' => written.extend((arg as u32).to_string().bytes()),
+            b'x' => written.extend(format!("{:x}", arg as u32).bytes()),
+            b'c' => written.push(arg as u8),
+            _ => return Err(format!("Unsupported `printf` conversion `%{}`", conversion as char)),
+        }
+    }
+    Ok(written)
+}
+
+/// Runs `main` and returns its result.
+pub fn interpret_tacky_program(tacky_program: &TackyProgram) -> Result<i32, String> {
+    Interpreter::new(tacky_program).call("main", &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[i32], memory: &[u8]) -> (Result<i32, String>, String) {
+        let mut out = Vec::new();
+        let result = call_host_function(name, args, memory, &mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn putchar() {
+        assert_eq!(call("putchar", &[b'A' as i32], &[]), (Ok(65), "A".into()));
+        assert_eq!(call("putchar", &[0x141], &[]), (Ok(0x41), "A".into()));
+    }
+
+    #[test]
+    fn printf() {
+        let memory = b"junk\0%d|%i|%u|%x|%c|100%%\n\0";
+        assert_eq!(call("printf", &[5, -7, 3, -1, 255, b'z' as i32], memory), (Ok(26), "-7|3|4294967295|ff|z|100%\n".into()));
+        assert!(call("printf", &[5], memory).0.is_err());
+        assert!(call("printf", &[64], memory).0.is_err());
+        assert!(call("printf", &[0], b"no terminator").0.is_err());
+    }
+
+    #[test]
+    fn undefined_function() {
+        assert!(call("puts", &[0], b"\0").0.is_err());
+    }
+}
@@ -1,4 +1,9 @@
 use std::fmt;
+use std::rc::Rc;
+
+use crate::diagnostics::{Diagnostic, INVALID_TOKEN, MALFORMED_LINE_MARKER};
+use crate::preprocessor::parse_string_literal;
+use crate::span::Span;
 
 use Keyword::*;
 use TokenSyntaxCheckResult::*;
@@ -11,9 +16,6 @@ pub struct Lexer {
 
 impl Lexer {
     pub fn tokens(&self) -> Tokens {
-        if !self.src.is_ascii() {
-            unimplemented!("Supporting Non-ASCII characters in source code");
-        }
         Tokens::from_src(&self.src)
     }
 
@@ -24,19 +26,70 @@ impl Lexer {
 
 pub struct Tokens<'a> {
     src: &'a str,
+    offset: usize,
+    line: u32,
+    line_start: usize,
+    file: Rc<str>,
 }
 
 impl<'a> Tokens<'a> {
     fn from_src(src: &'a str) -> Self {
-        Self { src }
+        Self { src, offset: 0, line: 1, line_start: 0, file: "".into() }
     }
 
+    fn advance(&mut self, len: usize) -> &'a str {
+        let consumed;
+        (consumed, self.src) = self.src.split_at(len);
+        for (i, ch) in consumed.char_indices() {
+            if ch == '\n' {
+                self.line += 1;
+                self.line_start = self.offset + i + 1;
+            }
+        }
+        self.offset += len;
+        consumed
+    }
+
+    fn span(&self, offset: usize, len: usize) -> Span {
+        Span { file: self.file.clone(), offset, len, line: self.line, column: (offset - self.line_start + 1) as u32 }
+    }
+
+    // A line marker `# <line> "<file>"` (or `#line <line> "<file>"`) sets the presumed position of the next line.
+    fn skip_line_marker(&mut self) -> Result<(), Diagnostic> {
+        let len = self.src.find('\n').unwrap_or(self.src.len());
+        let span = self.span(self.offset, len);
+        let marker = self.advance(len)[1..].trim_start();
+        let marker = marker.strip_prefix("line").unwrap_or(marker).trim();
+        let (line, file) = marker.split_once(' ').unwrap_or((marker, ""));
+        let line: u32 = line.parse().map_err(|_| {
+            Diagnostic::error(format!("malformed line marker `#{marker}`"))
+                .with_code(MALFORMED_LINE_MARKER)
+                .with_primary(span, "expected `# <line> \"<file>\"`")
+        })?;
+        // `gcc -E` follows the file name with flags such as `1 3 4`, which don't matter here.
+        if let Some(file) = file.trim().strip_prefix('"') {
+            let mut escaped = false;
+            let end = file.find(|ch| {
+                let end = ch == '"' && !escaped;
+                escaped = ch == '\\' && !escaped;
+                end
+            });
+            if let Some(end) = end {
+                self.file = parse_string_literal(&file[..end]).into();
+            }
+        }
+        // Consuming the newline moves on to `line`.
+        self.line = line.saturating_sub(1);
+        Ok(())
+    }
+
+    // The length in bytes of the next token, or of the invalid token up to and including the offending character.
     fn next_token_len(&self) -> Result<usize, usize> {
         let mut tsc = TokenSyntaxChecker::default();
-        for ch in self.src.chars() {
+        for (i, ch) in self.src.char_indices() {
             match tsc.check(ch) {
-                TokenEnd => { return Ok(tsc.len as _); },
-                TokenInvalid => { return Err(tsc.len as _); },
+                TokenEnd => { return Ok(i); },
+                TokenInvalid => { return Err(i + ch.len_utf8()); },
                 _ => {},
             }
         }
@@ -45,22 +98,37 @@ impl<'a> Tokens<'a> {
 }
 
 impl<'a> Iterator for Tokens<'a> {
-    type Item = Result<Token<'a>, String>;
+    type Item = Result<(Token<'a>, Span), Diagnostic>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.src = self.src.trim_ascii_start();
+        loop {
+            self.advance(self.src.len() - self.src.trim_ascii_start().len());
+            let at_line_start = self.offset == self.line_start;
+            if !(self.src.starts_with('#') && at_line_start) {
+                break;
+            }
+            if let Err(e) = self.skip_line_marker() {
+                return Some(Err(e));
+            }
+        }
         if self.src.is_empty() {
             return None;
         }
 
         match self.next_token_len() {
             Ok(len) => {
-                let token_str;
-                (token_str, self.src) = self.src.split_at(len);
-                return Some(Ok(Token::from(token_str)));
+                let span = self.span(self.offset, len);
+                let token_str = self.advance(len);
+                return Some(Ok((Token::from(token_str), span)));
             },
             Err(len) => {
-                return Some(Err(format!("Invalid token: `{}`", &self.src[..=len])));
+                // Skip the invalid token, so that lexing can go on after it.
+                let span = self.span(self.offset, len);
+                let token_str = self.advance(len);
+                let diagnostic = Diagnostic::error(format!("invalid token `{token_str}`"))
+                    .with_code(INVALID_TOKEN)
+                    .with_primary(span, "not a valid token");
+                return Some(Err(diagnostic));
             },
         }
     }
@@ -230,3 +298,36 @@ impl TryFrom<&str> for Keyword {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::pipeline::lex;
+
+    // Lexes `gcc -E` output as is, flags after the file names included.
+    #[test]
+    fn gcc_line_markers() {
+        let Err(diagnostics) = lex(include_str!("../tests/golden/spans/gcc_line_marker.c").into()) else {
+            panic!("That `$` should be an invalid token");
+        };
+        let spans: Vec<String> = diagnostics.iter().map(|diagnostic| diagnostic.primary().unwrap().span.to_string()).collect();
+        assert_eq!(spans, ["gcc_line_marker.c:3:12"]);
+
+        let Ok(lexer) = lex("# 7 \"a \\\"b\\\".h\" 1 3 4\nint".into()) else {
+            panic!("That the line marker should be valid");
+        };
+        let spans: Vec<String> = lexer.tokens().map(|token| token.unwrap().1.to_string()).collect();
+        assert_eq!(spans, ["a \"b\".h:7:1"]);
+    }
+
+    // Non-ASCII characters are invalid tokens, skipped as a whole so that lexing goes on after them.
+    #[test]
+    fn non_ascii() {
+        let Err(diagnostics) = lex("int é 1ü;\n€ return".into()) else {
+            panic!("That non-ASCII characters should be invalid tokens");
+        };
+        let messages: Vec<String> = diagnostics.iter()
+            .map(|diagnostic| format!("{}: {}", diagnostic.primary().unwrap().span, diagnostic.message))
+            .collect();
+        assert_eq!(messages, [":1:5: invalid token `é`", ":1:9: invalid token `ü`", ":2:1: invalid token `€`"]);
+    }
+}
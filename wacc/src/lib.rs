@@ -0,0 +1,91 @@
+//! # wacc
+//!
+//! A compiler for a small subset of C, following "Writing a C Compiler". The `wacc` binary is a gcc-like driver
+//! around this crate; tools can also compile in-process with `compile_str`, which touches no files except included
+//! headers:
+//! ```no_run
+//! let artifacts = wacc::compile_str("int main(void) { return 2; }", wacc::Options::default()).unwrap();
+//! println!("{}", artifacts.assembly);
+//! ```
+
+mod compiler_driver;
+mod pipeline;
+mod cleanup;
+mod preprocessor;
+mod lexer;
+mod parser;
+pub mod ast_nodes;
+mod span;
+mod diagnostics;
+mod warnings;
+mod tackygen;
+mod tacky_parser;
+mod optimizer;
+mod codegen;
+mod codegen_aarch64;
+mod codegen_riscv64;
+mod codegen_wasm;
+mod regalloc;
+mod frame;
+mod peephole;
+mod emit;
+mod debug_info;
+mod encode;
+mod elf;
+mod linker;
+mod emit_aarch64;
+mod emit_riscv64;
+mod emit_wasm;
+mod emit_llvm;
+mod interpreter;
+mod simulator;
+
+pub use compiler_driver::{CompilerDriver, CompilerDriverOption, Dump};
+pub use cleanup::interrupted;
+pub use pipeline::{Options, Target, TargetProgram};
+pub use diagnostics::{render_diagnostics, Diagnostic, ErrorFormat, Label, Severity};
+pub use warnings::{Warning, WarningOptions};
+pub use optimizer::Optimization;
+pub use emit::{AsmSyntax, EmitOptions, ObjectFormat};
+pub use elf::{ObjectFile, Relocation, RelocationKind, Symbol};
+pub use frame::{FrameLayout, FrameSlot};
+pub use span::Span;
+
+use ast_nodes::{CProgram, TackyProgram};
+use parser::Parser;
+use tackygen::gen_tacky_program;
+use optimizer::optimize_tacky_program;
+
+/// The errors that stopped a compilation.
+pub type Diagnostics = Vec<Diagnostic>;
+
+/// What each stage of `compile_str` produced.
+#[derive(Debug)]
+pub struct Artifacts {
+    pub preprocessed: String,
+    /// The tokens of the preprocessed source, as spelled there.
+    pub tokens: Vec<(String, Span)>,
+    pub ast: CProgram,
+    /// The TACKY program, after the enabled optimizations.
+    pub tacky: TackyProgram,
+    pub target_program: TargetProgram,
+    pub assembly: String,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Compiles the C source in `source` to assembly text, keeping every intermediate representation.
+pub fn compile_str(source: &str, options: Options) -> Result<Artifacts, Diagnostics> {
+    options.validate().map_err(|e| vec![Diagnostic::from(e)])?;
+    let preprocessed = options.preprocessor().preprocess_str(source, options.filename())
+        .map_err(|e| vec![Diagnostic::from(e)])?;
+    let lexer = pipeline::lex(preprocessed.clone())?;
+    let tokens = lexer.tokens().flatten().map(|(token, span)| (token.to_string(), span)).collect();
+    let ast = Parser::from(lexer.tokens()).parse()?;
+    let warnings = pipeline::check(&ast, options.warning_options())?;
+
+    let mut tacky = gen_tacky_program(ast.clone());
+    optimize_tacky_program(&mut tacky, options.optimizations());
+    let target_program = pipeline::codegen(tacky.clone(), &options);
+    let assembly = pipeline::emit(target_program.clone(), &options);
+    Ok(Artifacts { preprocessed, tokens, ast, tacky, target_program, assembly, warnings })
+}
@@ -0,0 +1,74 @@
+//! # Linker
+//!
+//! Links x86-64 relocatable objects into a static executable without libc. The `.text` sections are laid out one
+//! after the other behind a startup stub, which calls `main` and passes its result to the `exit` system call:
+//! ```text
+//! _start:
+//!     xorl %ebp, %ebp
+//!     call main
+//!     movl %eax, %edi
+//!     movl $60, %eax
+//!     syscall
+//! ```
+//! Linking fails on anything else an object needs, such as data sections or symbols from libc, so that the driver can
+//! leave those programs to gcc.
+
+use std::collections::HashMap;
+
+use crate::elf::{executable_text_offset, write_executable, ObjectFile, Relocation, RelocationKind, Symbol};
+
+const BASE_ADDRESS: u64 = 0x400000;
+
+fn start_stub(main: &str) -> ObjectFile {
+    let text = vec![
+        0x31, 0xed,
+        0xe8, 0, 0, 0, 0,
+        0x89, 0xc7,
+        0xb8, 60, 0, 0, 0,
+        0x0f, 0x05,
+    ];
+    let symbol = Symbol { name: "_start".into(), offset: 0, size: text.len() as u64 };
+    let call = Relocation { offset: 3, symbol: main.into(), kind: RelocationKind::Plt32, addend: -4 };
+    ObjectFile { text, symbols: vec![symbol], relocations: vec![call] }
+}
+
+/// Links `objects` into an executable whose stub calls the function named `main`.
+pub fn link_executable(objects: &[ObjectFile], main: &str) -> Result<Vec<u8>, String> {
+    let stub = start_stub(main);
+    let objects: Vec<&ObjectFile> = std::iter::once(&stub).chain(objects).collect();
+
+    let text_address = BASE_ADDRESS + executable_text_offset();
+    let mut text = Vec::new();
+    let mut starts = Vec::new();
+    for object in &objects {
+        text.resize(text.len().next_multiple_of(16), 0);
+        starts.push(text.len() as u64);
+        text.extend(&object.text);
+    }
+
+    let mut addresses = HashMap::new();
+    for (object, start) in objects.iter().zip(&starts) {
+        for symbol in &object.symbols {
+            if addresses.insert(symbol.name.as_str(), text_address + start + symbol.offset).is_some() {
+                return Err(format!("multiple definitions of `{}`", symbol.name));
+            }
+        }
+    }
+
+    for (object, start) in objects.iter().zip(&starts) {
+        for relocation in &object.relocations {
+            let target = addresses.get(relocation.symbol.as_str())
+                .ok_or_else(|| format!("undefined reference to `{}`", relocation.symbol))?;
+            // Without a PLT, calls go straight to the function, like PC-relative references do.
+            let place = text_address + start + relocation.offset;
+            let value = i32::try_from(*target as i64 + relocation.addend - place as i64)
+                .map_err(|_| format!("relocation against `{}` out of range", relocation.symbol))?;
+            let offset = (start + relocation.offset) as usize;
+            text.get_mut(offset..offset + 4)
+                .ok_or_else(|| format!("relocation against `{}` outside `.text`", relocation.symbol))?
+                .copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    Ok(write_executable(&text, text_address, text_address))
+}
@@ -1,40 +1,165 @@
-mod compiler_driver;
-use compiler_driver::{CompilerDriver, CompilerDriverOption::*};
-
-mod lexer;
-mod parser;
-mod ast_nodes;
-mod tackygen;
-mod codegen;
-mod emit;
+use wacc::{interrupted, render_diagnostics, CompilerDriver, CompilerDriverOption::*, Dump, ErrorFormat, Optimization::*, Severity};
 
 use std::env::args;
 use std::process::exit;
 
 fn main() {
     let mut compiler_driver = CompilerDriver::default();
+    let mut error_format = ErrorFormat::default();
 
-    for arg in args().skip(1) {
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
         match arg.as_str() {
             "-Sref"     => compiler_driver.set_option(EmitReferenceAssembly),
+            "-E"        => compiler_driver.set_option(Preprocess),
             "--lex"     => compiler_driver.set_option(Lex),
             "--parse"   => compiler_driver.set_option(Parse),
             "--codegen" => compiler_driver.set_option(Codegen),
             "--tacky"   => compiler_driver.set_option(Tacky),
             "-S"        => compiler_driver.set_option(EmitAssembly),
-            option => {
-                if option.starts_with('-') {
-                    eprintln!("Invalid option `{option}`");
-                    exit(1);
-                }
-                compiler_driver.set_filename(option)
-            }
+            "-c"        => compiler_driver.set_option(Assemble),
+            "-o"        => compiler_driver.set_output(&expect_value(&arg, args.next())),
+            "-O"        => compiler_driver.get_options_mut().enable_all_optimizations(),
+            "--eliminate-dead-stores" => compiler_driver.get_options_mut().enable_optimization(EliminateDeadStores),
+            "--peephole" => compiler_driver.get_options_mut().enable_optimization(Peephole),
+            "--emit-llvm" => compiler_driver.set_emit_llvm(),
+            "--from-tacky" => compiler_driver.set_from_tacky(),
+            "-g"        => compiler_driver.get_options_mut().get_emit_options_mut().enable_debug_info(),
+            "-fno-integrated-as" => compiler_driver.set_external_assembler(),
+            "-fuse-ld=gcc" => compiler_driver.set_external_linker(),
+            "--interpret" => compiler_driver.set_interpret(),
+            "--simulate" => compiler_driver.set_simulate(),
+            "--target"  => compiler_driver.get_options_mut().set_target(parse_value(&arg, args.next())),
+            "--asm-syntax" => compiler_driver.get_options_mut().get_emit_options_mut().set_syntax(parse_value(&arg, args.next())),
+            "--object-format" => compiler_driver.get_options_mut().get_emit_options_mut().set_object_format(parse_value(&arg, args.next())),
+            "--symbol-prefix" => compiler_driver.get_options_mut().get_emit_options_mut().set_symbol_prefix(&expect_value(&arg, args.next())),
+            "--error-format" => error_format = parse_value(&arg, args.next()),
+            option if option.starts_with("--error-format=") => {
+                error_format = parse_value("--error-format", Some(option["--error-format=".len()..].into()));
+            },
+            // Accepted like gcc's, but `-Wconversion`, the only warning, is in neither.
+            "-Wall" | "-Wextra" => {},
+            "-Werror"   => compiler_driver.get_options_mut().get_warning_options_mut().set_werror(),
+            option if option.starts_with("-Wno-") => {
+                compiler_driver.get_options_mut().get_warning_options_mut().set(parse_value(&arg, Some(option["-Wno-".len()..].into())), false);
+            },
+            option if option.starts_with("-W") => {
+                compiler_driver.get_options_mut().get_warning_options_mut().set(parse_value(&arg, Some(option["-W".len()..].into())), true);
+            },
+            "-I"        => compiler_driver.get_options_mut().add_include_path(&expect_value(&arg, args.next())),
+            option if option.starts_with("-I") => compiler_driver.get_options_mut().add_include_path(&option[2..]),
+            "-l" | "-L" => compiler_driver.add_linker_option(format!("{arg}{}", expect_value(&arg, args.next()))),
+            option if option.starts_with("-l") || option.starts_with("-L") => compiler_driver.add_linker_option(arg.clone()),
+            "-v" | "--verbose" => compiler_driver.set_verbose(),
+            option if option.starts_with("--dump-") => {
+                let (dump, path) = match option["--dump-".len()..].split_once('=') {
+                    Some((dump, path)) => (dump, Some(path)),
+                    None => (&option["--dump-".len()..], None),
+                };
+                compiler_driver.add_dump(parse_value::<Dump>("--dump-", Some(dump.into())), path);
+            },
+            "--help"    => {
+                print!("{USAGE}");
+                exit(0);
+            },
+            "--version" => {
+                println!("wacc {}", env!("CARGO_PKG_VERSION"));
+                exit(0);
+            },
+            option if option.starts_with('-') => {
+                eprintln!("Unrecognized option `{option}`; run `wacc --help` for the list of options");
+                exit(1);
+            },
+            input => compiler_driver.add_input(input),
         }
     }
 
-    if let Err(e) = compiler_driver.run() {
-        eprintln!("Failed: {e}");
+    let result = compiler_driver.run();
+    if interrupted() {
+        exit(130);
+    }
+    let mut diagnostics = compiler_driver.take_warnings();
+    if let Err(errors) = result {
+        diagnostics.extend(errors);
+        eprint!("{}", render_diagnostics(&diagnostics, error_format));
+        let diagnostics: Vec<_> = diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).collect();
+        if error_format == ErrorFormat::Human {
+            eprintln!("Failed: {} error{}", diagnostics.len(), if diagnostics.len() == 1 { "" } else { "s" });
+        }
         exit(1);
     }
-    println!("Succeeded");
+    eprint!("{}", render_diagnostics(&diagnostics, error_format));
+    exit(compiler_driver.exit_status());
+}
+
+const USAGE: &str = "\
+Usage: wacc [options] <file>...
+
+Compiles `.c` files, assembles `.s` files and links them with `.o` files into an executable.
+
+Stages:
+  -E                   Preprocess only, into `<file>.i`
+  --lex                Stop after lexing
+  --parse              Stop after parsing
+  --tacky              Stop after generating TACKY
+  --codegen            Stop after generating assembly
+  -S                   Compile only, into `<file>.s`
+  -c                   Compile and assemble, into `<file>.o`
+  -Sref                Compile with gcc into `<file>.s`, for reference
+
+Output:
+  -o <path>            Write the output to <path>
+  -l<library>          Link with <library>
+  -L<dir>              Search <dir> for libraries
+  -I<dir>              Search <dir> for included headers
+
+Code generation:
+  -O                   Enable all optimizations
+  --eliminate-dead-stores
+                       Enable dead store elimination
+  --peephole           Enable the peephole optimizer
+  --target <triple>    x86_64-linux-gnu, aarch64-linux-gnu, riscv64-linux-gnu or wasm32
+  --asm-syntax <syntax>
+                       att or intel
+  --object-format <format>
+                       elf or mach-o
+  --symbol-prefix <prefix>
+                       Prefix global symbols with <prefix>
+  --emit-llvm          Emit LLVM IR into `<file>.ll`
+  -g                   Generate DWARF debug info for gdb (x86-64 ELF only; assembles with gcc)
+  -fno-integrated-as   Assemble with gcc instead of writing x86-64 ELF objects directly
+  -fuse-ld=gcc         Link with gcc even when the built-in static linker could
+  --from-tacky         Read `.tacky` files in the textual TACKY format and run only the backend
+  --interpret          Run the program's TACKY and exit with its status
+  --simulate           Run the program's assembly and exit with its status
+
+Debugging:
+  -v, --verbose        Print the commands run to stderr
+  --dump-tokens[=<file>], --dump-ast[=<file>], --dump-tacky[=<file>], --dump-asm[=<file>]
+                       Print the tokens, C AST, TACKY or assembly IR to stdout or <file>
+
+Diagnostics:
+  -Wall, -Wextra       Accepted for gcc compatibility; enable no warning
+  -W<name>, -Wno-<name>
+                       Enable or disable a single warning
+  -Werror              Treat warnings as errors
+  --error-format <format>
+                       human, json or sarif
+
+  --help               Print this help
+  --version            Print the version
+";
+
+fn expect_value(option: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| {
+        eprintln!("Option `{option}` expects a value");
+        exit(1);
+    })
+}
+
+fn parse_value<T: for<'a> TryFrom<&'a str, Error = String>>(option: &str, value: Option<String>) -> T {
+    T::try_from(expect_value(option, value).as_str()).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    })
 }
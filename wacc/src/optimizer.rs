@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use crate::ast_nodes::*;
+
+use Optimization::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Optimization {
+    EliminateDeadStores,
+    Peephole,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Optimizations {
+    eliminate_dead_stores: bool,
+    peephole: bool,
+}
+
+impl Optimizations {
+    pub fn enable(&mut self, optimization: Optimization) {
+        match optimization {
+            EliminateDeadStores => self.eliminate_dead_stores = true,
+            Peephole => self.peephole = true,
+        }
+    }
+
+    pub fn enable_all(&mut self) {
+        self.enable(EliminateDeadStores);
+        self.enable(Peephole);
+    }
+
+    pub fn any_tacky(&self) -> bool {
+        self.eliminate_dead_stores
+    }
+
+    pub fn peephole(&self) -> bool {
+        self.peephole
+    }
+}
+
+/// Runs every enabled TACKY optimization repeatedly until none of them changes the program any more.
+pub fn optimize_tacky_program(tacky_program: &mut TackyProgram, optimizations: &Optimizations) {
+    let tacky::Program(tacky::Function(_, instructions, _)) = tacky_program;
+    loop {
+        let mut changed = false;
+        if optimizations.eliminate_dead_stores {
+            changed |= eliminate_dead_stores(instructions);
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+fn eliminate_dead_stores(instructions: &mut Vec<TackyInstruction>) -> bool {
+    let live_after = live_variables(instructions);
+    let len_before = instructions.len();
+    let mut live_after = live_after.into_iter();
+    instructions.retain(|instruction| {
+        let live = live_after.next().unwrap();
+        match defined_variable(instruction) {
+            Some(name) => has_side_effects(instruction) || live.contains(name),
+            None => true,
+        }
+    });
+    instructions.len() != len_before
+}
+
+/// Backward liveness analysis: returns the set of variables live right after each instruction.
+///
+/// TACKY function bodies are straight-line code for now (no labels or jumps), so a single backward sweep
+/// reaches the fixpoint.
+pub fn live_variables(instructions: &[TackyInstruction]) -> Vec<HashSet<String>> {
+    let mut live_after = vec![HashSet::new(); instructions.len()];
+    let mut live = HashSet::new();
+    for (i, instruction) in instructions.iter().enumerate().rev() {
+        live_after[i] = live.clone();
+        if let Some(name) = defined_variable(instruction) {
+            live.remove(name);
+        }
+        for name in used_variables(instruction) {
+            live.insert(name.clone());
+        }
+    }
+    live_after
+}
+
+fn defined_variable(instruction: &TackyInstruction) -> Option<&String> {
+    match instruction {
+        tacky::Return(..) => None,
+        TackyInstruction::Unary(_, _, dst, _) => variable_name(dst),
+    }
+}
+
+fn used_variables(instruction: &TackyInstruction) -> Vec<&String> {
+    match instruction {
+        tacky::Return(val, _) => variable_name(val).into_iter().collect(),
+        TackyInstruction::Unary(_, src, ..) => variable_name(src).into_iter().collect(),
+    }
+}
+
+fn variable_name(operand: &TackyOperand) -> Option<&String> {
+    match operand {
+        tacky::Constant(_) => None,
+        tacky::Variable(tacky::Identifier(name)) => Some(name),
+    }
+}
+
+// Whether the instruction does more than write its destination, so that it stays even when the destination is dead.
+fn has_side_effects(instruction: &TackyInstruction) -> bool {
+    match instruction {
+        tacky::Return(..) => true,
+        TackyInstruction::Unary(..) => false,
+    }
+}
+
+// C programs are a single `return` so far and never store anything dead, so the pass is checked on TACKY directly.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+
+    fn variable(name: &str) -> TackyOperand {
+        tacky::Variable(tacky::Identifier(name.into()))
+    }
+
+    fn unary(operator: TackyUnaryOperator, src: TackyOperand, dst: &str) -> TackyInstruction {
+        TackyInstruction::Unary(operator, src, variable(dst), Span::default())
+    }
+
+    fn ret(src: TackyOperand) -> TackyInstruction {
+        tacky::Return(src, Span::default())
+    }
+
+    #[test]
+    fn eliminates_dead_stores() {
+        // `unused` is never read, `y` only by the dead store to `z`, and the first store to `x` is overwritten first.
+        let mut tacky_program = tacky::Program(tacky::Function(tacky::Identifier("main".into()), vec![
+            unary(tacky::Negate, tacky::Constant(1), "unused"),
+            unary(tacky::Negate, tacky::Constant(2), "x"),
+            unary(tacky::Negate, tacky::Constant(5), "live"),
+            unary(tacky::Complement, variable("live"), "y"),
+            unary(tacky::Negate, variable("y"), "z"),
+            unary(tacky::Complement, variable("live"), "x"),
+            ret(variable("x")),
+        ], Span::default()));
+        let mut optimizations = Optimizations::default();
+        optimizations.enable(EliminateDeadStores);
+        optimize_tacky_program(&mut tacky_program, &optimizations);
+
+        let tacky::Program(tacky::Function(_, instructions, _)) = tacky_program;
+        let expected = [
+            unary(tacky::Negate, tacky::Constant(5), "live"),
+            unary(tacky::Complement, variable("live"), "x"),
+            ret(variable("x")),
+        ];
+        assert_eq!(format!("{instructions:?}"), format!("{expected:?}"));
+    }
+
+    #[test]
+    fn keeps_live_stores() {
+        let mut instructions = vec![
+            unary(tacky::Complement, tacky::Constant(2), "a"),
+            unary(tacky::Negate, variable("a"), "b"),
+            ret(variable("b")),
+        ];
+        assert!(!eliminate_dead_stores(&mut instructions));
+        assert_eq!(instructions.len(), 3);
+    }
+}
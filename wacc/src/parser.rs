@@ -1,83 +1,191 @@
+use std::iter::Peekable;
+
 use crate::lexer::{Token, Tokens};
 use crate::ast_nodes::*;
+use crate::diagnostics::*;
+use crate::span::Span;
 
 pub struct Parser<'a> {
-    tokens: Tokens<'a>,
+    tokens: Peekable<Tokens<'a>>,
+    last_span: Option<Span>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> From<Tokens<'a>> for Parser<'a> {
     fn from(tokens: Tokens<'a>) -> Self {
-        Self { tokens }
+        Self { tokens: tokens.peekable(), last_span: None, diagnostics: Vec::new() }
     }
 }
 
 impl<'a> Parser<'a> {
-    pub fn parse(&mut self) -> Result<CProgram, String> {
-        self.parse_program()
+    /// Parses the whole program, recovering from errors to report as many of them as possible.
+    pub fn parse(&mut self) -> Result<CProgram, Vec<Diagnostic>> {
+        match self.parse_program() {
+            Some(c_program) if self.diagnostics.is_empty() => Ok(c_program),
+            _ => Err(std::mem::take(&mut self.diagnostics)),
+        }
+    }
+
+    // Consumes the next token if `accept` takes it; an unexpected token is left for error recovery.
+    fn next_token_if(&mut self, expected: &str, accept: impl Fn(&Token) -> bool) -> Result<(Token<'a>, Span), Diagnostic> {
+        match self.tokens.peek() {
+            None => {
+                let end = self.last_span.as_ref()
+                    .map(|span| Span { offset: span.offset + span.len, column: span.column + span.len as u32, len: 0, ..span.clone() })
+                    .unwrap_or_default();
+                Err(Diagnostic::error(format!("expected {expected}, found end of input"))
+                    .with_code(UNEXPECTED_END_OF_INPUT)
+                    .with_primary(end, format!("expected {expected}")))
+            },
+            Some(Ok((token, span))) if !accept(token) => {
+                let mut diagnostic = Diagnostic::error(format!("expected {expected}, found `{token}`"))
+                    .with_code(UNEXPECTED_TOKEN)
+                    .with_primary(span.clone(), format!("unexpected `{token}`"));
+                if let Some(last_span) = self.last_span.clone().filter(|last_span| last_span.line != span.line) {
+                    diagnostic = diagnostic.with_secondary(last_span, format!("expected {expected} after this"));
+                }
+                Err(diagnostic)
+            },
+            Some(_) => {
+                let (token, span) = self.tokens.next().unwrap()?;
+                self.last_span = Some(span.clone());
+                Ok((token, span))
+            },
+        }
     }
 
     // TODO: parse_identifier() and parse_expression() have similar code structure
-    fn expect_next(&mut self, expected: Token) -> Result<(), String> {
-        let next_token = self.tokens.next()
-            .unwrap_or(Err(format!("Expect `{expected}` but no tokens left")))?;
-        if next_token != expected {
-            return Err(format!("Expect `{expected}`, found `{next_token}`"));
+    fn expect_next(&mut self, expected: Token) -> Result<Span, Diagnostic> {
+        let (_, span) = self.next_token_if(&format!("`{expected}`"), |token| *token == expected)?;
+        Ok(span)
+    }
+
+    // Once the input has ended, every construct still open reports it again; only the first report is kept.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        let ended = |diagnostic: &Diagnostic| diagnostic.code == Some(UNEXPECTED_END_OF_INPUT);
+        if !(ended(&diagnostic) && self.diagnostics.iter().any(ended)) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    // Skips to the end of the current statement: past the next `;`, or up to the `}` closing the block.
+    fn synchronize_statement(&mut self) {
+        while let Some(next) = self.tokens.peek() {
+            match next {
+                Ok((Token::CloseBrace, _)) => return,
+                Ok((Token::Semicolon, _)) => {
+                    self.tokens.next();
+                    return;
+                },
+                _ => { self.tokens.next(); },
+            }
+        }
+    }
+
+    // Skips past the `}` closing the current function.
+    fn synchronize_function(&mut self) {
+        for next in self.tokens.by_ref() {
+            if let Ok((Token::CloseBrace, _)) = next {
+                return;
+            }
         }
-        Ok(())
     }
 
-    fn parse_program(&mut self) -> Result<CProgram, String> {
-        let function_definition = self.parse_function_definition()?;
-        if self.tokens.next().is_some() {
-            return Err("Expect no tokens after function".into());
+    fn parse_program(&mut self) -> Option<CProgram> {
+        let function_definition = self.parse_function_definition();
+        if let Some(next) = self.tokens.next() {
+            let diagnostic = match next {
+                Ok((token, span)) => Diagnostic::error(format!("expected end of input after the function, found `{token}`"))
+                    .with_code(TRAILING_TOKENS)
+                    .with_primary(span, "unexpected token after the function")
+                    .with_note("a program consists of a single function definition"),
+                Err(diagnostic) => diagnostic,
+            };
+            self.report(diagnostic);
         }
-        Ok(c::Program(function_definition))
+        Some(c::Program(function_definition?))
+    }
+
+    fn parse_function_definition(&mut self) -> Option<CFunctionDefinition> {
+        let (start, name) = match self.parse_function_header() {
+            Ok(header) => header,
+            Err(diagnostic) => {
+                self.report(diagnostic);
+                self.synchronize_function();
+                return None;
+            },
+        };
+        let statement = match self.parse_statement() {
+            Ok(statement) => Some(statement),
+            Err(diagnostic) => {
+                self.report(diagnostic);
+                self.synchronize_statement();
+                None
+            },
+        };
+        let end = match self.expect_next(Token::from("}")) {
+            Ok(end) => end,
+            Err(diagnostic) => {
+                self.report(diagnostic);
+                self.synchronize_function();
+                return None;
+            },
+        };
+        Some(c::Function(name, statement?, start.to(&end)))
     }
 
-    fn parse_function_definition(&mut self) -> Result<CFunctionDefinition, String> {
-        self.expect_next(Token::from("int"))?;
+    fn parse_function_header(&mut self) -> Result<(Span, CIdentifier), Diagnostic> {
+        let start = self.expect_next(Token::from("int"))?;
         let name = self.parse_identifier()?;
         self.expect_next(Token::from("("))?;
         self.expect_next(Token::from("void"))?;
         self.expect_next(Token::from(")"))?;
         self.expect_next(Token::from("{"))?;
-        let statement = self.parse_statement()?;
-        self.expect_next(Token::from("}"))?;
-        Ok(c::Function(name, statement))
+        Ok((start, name))
     }
 
     // TODO: similar code structure with expect_next
-    fn parse_identifier(&mut self) -> Result<CIdentifier, String> {
-        let next_token = self.tokens.next()
-            .unwrap_or(Err("Expect an identifier but no tokens left".into()))?;
+    fn parse_identifier(&mut self) -> Result<CIdentifier, Diagnostic> {
+        let (next_token, span) = self.next_token_if("an identifier", |token| matches!(token, Token::Identifier(_)))?;
         let Token::Identifier(identifier) = next_token else {
-            return Err(format!("Expect an identifier, found `{next_token}`"));
+            unreachable!("Only identifiers should be accepted");
         };
-        Ok(c::Identifier(identifier.to_string()))
+        Ok(c::Identifier(identifier.to_string(), span))
     }
 
-    fn parse_statement(&mut self) -> Result<CStatement, String> {
-        self.expect_next(Token::from("return"))?;
+    fn parse_statement(&mut self) -> Result<CStatement, Diagnostic> {
+        let start = self.expect_next(Token::from("return"))?;
         let expression = self.parse_expression()?;
-        self.expect_next(Token::from(";"))?;
-        Ok(c::Return(expression))
+        let end = self.expect_next(Token::from(";"))?;
+        Ok(c::Return(expression, start.to(&end)))
     }
 
     // TODO: similar code structure with expect_next
-    fn parse_expression(&mut self) -> Result<CExpression, String> {
-        let next_token = self.tokens.next()
-            .unwrap_or(Err("Expect an expression but no tokens left".into()))?;
+    fn parse_expression(&mut self) -> Result<CExpression, Diagnostic> {
+        let starts_expression = |token: &Token| matches!(token,
+            Token::Constant(_) | Token::Complement | Token::Negate | Token::OpenParenthesis);
+        let decrement = matches!(self.tokens.peek(), Some(Ok((Token::Decrement, _))));
+        let (next_token, span) = self.next_token_if("an expression", starts_expression).map_err(|diagnostic| {
+            match diagnostic.code {
+                Some(UNEXPECTED_TOKEN) if decrement => Diagnostic { code: Some(EXPECTED_EXPRESSION), ..diagnostic }
+                    .with_help("`--` is the decrement operator; write `-(-x)` to negate twice"),
+                Some(UNEXPECTED_TOKEN) => Diagnostic { code: Some(EXPECTED_EXPRESSION), ..diagnostic },
+                _ => diagnostic,
+            }
+        })?;
         match next_token {
             Token::Constant(integer) => {
-                Ok(c::Constant(integer))
+                Ok(c::Constant(integer, span))
             },
             Token::Complement => {
                 let inner_expression = Box::new(self.parse_expression()?);
-                Ok(c::Unary(c::Complement, inner_expression))
+                let span = span.to(self.last_span.as_ref().unwrap());
+                Ok(c::Unary(c::Complement, inner_expression, span))
             },
             Token::Negate => {
                 let inner_expression = Box::new(self.parse_expression()?);
-                Ok(c::Unary(c::Negate, inner_expression))
+                let span = span.to(self.last_span.as_ref().unwrap());
+                Ok(c::Unary(c::Negate, inner_expression, span))
             },
             Token::OpenParenthesis => {
                 let inner_expression = self.parse_expression()?;
@@ -85,7 +193,7 @@ impl<'a> Parser<'a> {
                 Ok(inner_expression)
             },
             _ => {
-                Err(format!("Malformed expression: `{next_token}`"))
+                unreachable!("Only tokens starting an expression should be accepted");
             },
         }
     }
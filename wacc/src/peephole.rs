@@ -0,0 +1,49 @@
+//! # Peephole optimizer on the assembly IR
+//!
+//! Rewrites short windows of `AsmInstruction`s until nothing changes:
+//! - `mov x, x` is removed;
+//! - `mov $0, r` becomes `xor r, r`, since no instruction of the asm IR reads the flags it clobbers;
+//! - consecutive stack allocations are merged, and empty ones removed;
+//! - a load from the location just stored to reuses the stored register instead, which collapses the
+//!   load-then-store chains produced by `fix_invalid_mov_instructions`.
+
+use crate::ast_nodes::*;
+
+pub fn optimize_asm_program(asm_program: &mut AsmProgram) {
+    let asm::Program(asm::Function(_, instructions, ..)) = asm_program;
+    while optimize_instructions(instructions) {}
+}
+
+fn optimize_instructions(instructions: &mut Vec<AsmInstruction>) -> bool {
+    let mut changed = false;
+    let mut optimized: Vec<AsmInstruction> = Vec::with_capacity(instructions.len());
+    for instruction in instructions.drain(..) {
+        let previous = optimized.last_mut();
+        match (previous, instruction) {
+            (_, asm::Mov(src, dst, _)) if src == dst => {
+                changed = true;
+            },
+            (_, asm::Mov(asm::Imm(0), asm::Register(reg), span)) => {
+                optimized.push(asm::Binary(asm::Xor, asm::Register(reg), asm::Register(reg), span));
+                changed = true;
+            },
+            (_, asm::AllocateStack(0, _)) => {
+                changed = true;
+            },
+            (Some(asm::AllocateStack(previous, _)), asm::AllocateStack(bytes, _)) => {
+                *previous += bytes;
+                changed = true;
+            },
+            (Some(asm::Mov(asm::Register(reg), stored, _)), asm::Mov(loaded, dst, span)) if *stored == loaded => {
+                let reg = *reg;
+                optimized.push(asm::Mov(asm::Register(reg), dst, span));
+                changed = true;
+            },
+            (_, instruction) => {
+                optimized.push(instruction);
+            },
+        }
+    }
+    *instructions = optimized;
+    changed
+}
@@ -0,0 +1,212 @@
+//! # Compilation pipeline
+//!
+//! The stages between source text and assembly text, shared by `compile_str` and the `CompilerDriver`. They work in
+//! memory: reading inputs other than headers and writing outputs is left to their callers.
+
+use crate::preprocessor::Preprocessor;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::warnings::{check_c_program, WarningOptions};
+use crate::lexer::Lexer;
+use crate::ast_nodes::*;
+use crate::optimizer::{Optimization, Optimizations};
+use crate::codegen::gen_asm_program;
+use crate::peephole::optimize_asm_program;
+use crate::emit::{emit_asm_program, EmitOptions, ObjectFormat};
+use crate::codegen_aarch64::gen_aarch64_program;
+use crate::emit_aarch64::emit_aarch64_program;
+use crate::codegen_riscv64::gen_riscv64_program;
+use crate::emit_riscv64::emit_riscv64_program;
+use crate::codegen_wasm::gen_wasm_module;
+use crate::emit_wasm::emit_wasm_module;
+
+use Target::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Target {
+    #[default]
+    X86_64,
+    Aarch64,
+    Riscv64,
+    Wasm32,
+}
+
+impl Target {
+    // WebAssembly modules are loaded by a runtime as-is, so there is nothing to assemble or link.
+    pub(crate) fn gcc(&self) -> Option<&'static str> {
+        match self {
+            X86_64 => Some("gcc"),
+            Aarch64 => Some("aarch64-linux-gnu-gcc"),
+            Riscv64 => Some("riscv64-linux-gnu-gcc"),
+            Wasm32 => None,
+        }
+    }
+
+    pub(crate) fn assembly_extension(&self) -> &'static str {
+        match self {
+            Wasm32 => "wat",
+            _ => "s",
+        }
+    }
+}
+
+impl TryFrom<&str> for Target {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "x86_64-linux-gnu" | "x86_64-pc-linux-gnu" | "x86_64-unknown-linux-gnu" => Ok(X86_64),
+            "aarch64-linux-gnu" | "aarch64-unknown-linux-gnu" => Ok(Aarch64),
+            "riscv64-linux-gnu" | "riscv64-unknown-linux-gnu" => Ok(Riscv64),
+            "wasm32" | "wasm32-unknown-unknown" => Ok(Wasm32),
+            _ => Err(format!("Unsupported target `{value}`")),
+        }
+    }
+}
+
+/// The program generated for the target, before it's emitted as assembly text.
+#[derive(Debug, Clone)]
+pub enum TargetProgram {
+    X86_64(AsmProgram),
+    Aarch64(Aarch64Program),
+    Riscv64(Riscv64Program),
+    Wasm32(WasmModule),
+}
+
+impl TargetProgram {
+    pub(crate) fn dump(&self) -> String {
+        match self {
+            Self::X86_64(asm_program) => {
+                let asm::Program(asm::Function(asm::Identifier(name), _, frame_layout, _)) = asm_program;
+                format!("{asm_program}\nFrame layout of `{name}`:\n{frame_layout}")
+            },
+            Self::Aarch64(aarch64_program) => format!("{aarch64_program:#?}\n"),
+            Self::Riscv64(riscv64_program) => format!("{riscv64_program:#?}\n"),
+            Self::Wasm32(wasm_module) => format!("{wasm_module:#?}\n"),
+        }
+    }
+}
+
+/// How to compile: what the command-line options other than stages, inputs and outputs select.
+#[derive(Default, Clone)]
+pub struct Options {
+    filename: Option<String>,
+    include_paths: Vec<String>,
+    warning_options: WarningOptions,
+    optimizations: Optimizations,
+    target: Target,
+    emit_options: EmitOptions,
+}
+
+impl Options {
+    /// Names the source given to `compile_str` in spans; quoted includes are looked up next to it.
+    pub fn set_filename(&mut self, filename: &str) {
+        self.filename = Some(filename.into());
+    }
+
+    pub(crate) fn filename(&self) -> &str {
+        self.filename.as_deref().unwrap_or("<source>")
+    }
+
+    pub fn add_include_path(&mut self, path: &str) {
+        self.include_paths.push(path.into());
+    }
+
+    pub fn get_warning_options_mut(&mut self) -> &mut WarningOptions {
+        &mut self.warning_options
+    }
+
+    pub(crate) fn warning_options(&self) -> &WarningOptions {
+        &self.warning_options
+    }
+
+    pub fn enable_optimization(&mut self, optimization: Optimization) {
+        self.optimizations.enable(optimization);
+    }
+
+    pub fn enable_all_optimizations(&mut self) {
+        self.optimizations.enable_all();
+    }
+
+    pub(crate) fn optimizations(&self) -> &Optimizations {
+        &self.optimizations
+    }
+
+    pub fn set_target(&mut self, target: Target) {
+        self.target = target;
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    pub fn get_emit_options_mut(&mut self) -> &mut EmitOptions {
+        &mut self.emit_options
+    }
+
+    pub(crate) fn emit_options(&self) -> &EmitOptions {
+        &self.emit_options
+    }
+
+    // The debug info is written for gas and for ELF objects.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.emit_options.debug_info() && (self.target != X86_64 || self.emit_options.object_format() != ObjectFormat::Elf) {
+            return Err("`-g` is only supported for x86-64 ELF".into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn preprocessor(&self) -> Preprocessor {
+        let mut preprocessor = Preprocessor::default();
+        for path in &self.include_paths {
+            preprocessor.add_include_path(path);
+        }
+        preprocessor
+    }
+}
+
+/// Lexes the whole preprocessed source up front, so that every invalid token is reported at once.
+pub(crate) fn lex(preprocessed: String) -> Result<Lexer, Vec<Diagnostic>> {
+    let mut lexer = Lexer::default();
+    *lexer.get_src_mut() = preprocessed;
+
+    let diagnostics: Vec<Diagnostic> = lexer.tokens().filter_map(Result::err).collect();
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    Ok(lexer)
+}
+
+/// Returns the warnings on `c_program`, or fails with them as errors under `-Werror`.
+pub(crate) fn check(c_program: &CProgram, warning_options: &WarningOptions) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+    let warnings = check_c_program(c_program, warning_options);
+    if warning_options.werror() && !warnings.is_empty() {
+        return Err(warnings.into_iter()
+            .map(|warning| Diagnostic { severity: Severity::Error, ..warning }.with_note("`-Werror` turns warnings into errors"))
+            .collect());
+    }
+    Ok(warnings)
+}
+
+pub(crate) fn codegen(tacky_program: TackyProgram, options: &Options) -> TargetProgram {
+    match options.target {
+        X86_64 => {
+            let mut asm_program = gen_asm_program(tacky_program);
+            if options.optimizations.peephole() {
+                optimize_asm_program(&mut asm_program);
+            }
+            TargetProgram::X86_64(asm_program)
+        },
+        Aarch64 => TargetProgram::Aarch64(gen_aarch64_program(tacky_program)),
+        Riscv64 => TargetProgram::Riscv64(gen_riscv64_program(tacky_program)),
+        Wasm32 => TargetProgram::Wasm32(gen_wasm_module(tacky_program)),
+    }
+}
+
+pub(crate) fn emit(target_program: TargetProgram, options: &Options) -> String {
+    match target_program {
+        TargetProgram::X86_64(asm_program) => emit_asm_program(asm_program, &options.emit_options),
+        TargetProgram::Aarch64(aarch64_program) => emit_aarch64_program(aarch64_program),
+        TargetProgram::Riscv64(riscv64_program) => emit_riscv64_program(riscv64_program),
+        TargetProgram::Wasm32(wasm_module) => emit_wasm_module(wasm_module),
+    }
+}
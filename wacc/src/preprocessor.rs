@@ -0,0 +1,999 @@
+//! # C preprocessor
+//!
+//! Replaces `gcc -E -P` in the front end:
+//! - Line splices and comments are removed first, keeping the line count so that `__LINE__` stays right;
+//! - `#include` searches the including file's directory (for `"..."` only), then the `-I` paths, then the system
+//!   include directories, and honours `#pragma once`;
+//! - Object- and function-like macros (including variadic ones, `#` and `##`) are expanded with hide sets as in
+//!   Prosser's algorithm, so a macro is never expanded inside its own expansion;
+//! - `#if`/`#elif` expressions are evaluated in `intmax_t`, or `uintmax_t` when an operand is unsigned, after replacing
+//!   `defined` and expanding macros; `&&`, `||` and `?:` skip the errors of the operands they don't evaluate.
+//!
+//! The output keeps one line per source line, and line markers like `gcc -E` leaves them wherever the presumed file or
+//! line changes: when entering and leaving an `#include` and after `#line`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SYSTEM_INCLUDE_PATHS: [&str; 2] = ["/usr/local/include", "/usr/include"];
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PpTokenKind {
+    Identifier,
+    Number,
+    CharConstant,
+    StringLiteral,
+    Punctuator,
+    Other,
+    Newline,
+    Placemarker,
+}
+
+#[derive(Debug, Clone)]
+struct PpToken {
+    kind: PpTokenKind,
+    text: String,
+    leading_space: bool,
+    line: u32,
+    // Where the token starts in its source line; 0 for tokens the preprocessor makes up.
+    column: usize,
+    hideset: Vec<String>,
+}
+
+impl PpToken {
+    fn new(kind: PpTokenKind, text: &str, leading_space: bool, line: u32) -> Self {
+        Self { kind, text: text.into(), leading_space, line, column: 0, hideset: Vec::new() }
+    }
+
+    fn is(&self, punctuator: &str) -> bool {
+        self.kind == PpTokenKind::Punctuator && self.text == punctuator
+    }
+}
+
+// The tokens of one macro argument.
+type Arg = Vec<PpToken>;
+
+#[derive(Debug, Clone)]
+enum Macro {
+    Object(Vec<PpToken>),
+    Function(Vec<String>, bool, Vec<PpToken>),
+}
+
+struct Conditional {
+    active: bool,
+    taken: bool,
+    parent_active: bool,
+    seen_else: bool,
+}
+
+#[derive(Default)]
+pub struct Preprocessor {
+    include_paths: Vec<PathBuf>,
+    macros: HashMap<String, Macro>,
+    once_files: HashSet<PathBuf>,
+    include_depth: usize,
+    presumed_file: String,
+}
+
+impl Preprocessor {
+    pub fn add_include_path(&mut self, path: &str) {
+        self.include_paths.push(path.into());
+    }
+
+    pub fn preprocess(&mut self, filename: &str) -> Result<String, String> {
+        let src = fs::read_to_string(filename).map_err(|e| format!("Failed to read `{filename}`: {e}"))?;
+        self.preprocess_str(&src, filename)
+    }
+
+    /// Preprocesses `src` as the contents of `filename`, which needn't exist.
+    pub fn preprocess_str(&mut self, src: &str, filename: &str) -> Result<String, String> {
+        for (name, value) in [("__STDC__", "1"), ("__STDC_VERSION__", "201710L"), ("__STDC_HOSTED__", "1")] {
+            self.macros.insert(name.into(), Macro::Object(tokenize(value, 0)));
+        }
+        let mut output = String::new();
+        self.process(src, Path::new(filename), &mut output)?;
+        Ok(output)
+    }
+
+    fn process_file(&mut self, path: &Path, output: &mut String) -> Result<(), String> {
+        let src = fs::read_to_string(path).map_err(|e| format!("Failed to read `{}`: {e}", path.display()))?;
+        self.process(&src, path, output)
+    }
+
+    fn process(&mut self, src: &str, path: &Path, output: &mut String) -> Result<(), String> {
+        if self.include_depth > MAX_INCLUDE_DEPTH {
+            return Err(format!("`#include` nested too deeply at `{}`", path.display()));
+        }
+        let saved_file = std::mem::replace(&mut self.presumed_file, path.display().to_string());
+        self.include_depth += 1;
+        output.push_str(&self.line_marker(1));
+        let result = self.process_source(src, path, output);
+        self.include_depth -= 1;
+        self.presumed_file = saved_file;
+        result
+    }
+
+    fn process_source(&mut self, src: &str, path: &Path, output: &mut String) -> Result<(), String> {
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        let mut pending = Vec::new();
+        let mut line_delta: i64 = 0;
+
+        for (index, line) in remove_comments_and_splices(src).lines().enumerate() {
+            let line_number = (index as i64 + 1 + line_delta) as u32;
+            let active = conditionals.last().is_none_or(|conditional| conditional.active);
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    pending.extend(tokenize(line, line_number));
+                }
+                pending.push(PpToken::new(PpTokenKind::Newline, "\n", false, line_number));
+                continue;
+            };
+
+            self.flush(&mut pending, output).map_err(|e| format!("{}:{e}", self.presumed_file))?;
+            let error_prefix = format!("{}:{line_number}", self.presumed_file);
+            let tokens = tokenize(directive, line_number);
+            let name = tokens.first().map(|token| token.text.as_str()).unwrap_or_default();
+            let rest = tokens.get(1..).unwrap_or_default().to_vec();
+            match name {
+                "if" | "ifdef" | "ifndef" => {
+                    let condition = if !active {
+                        false
+                    } else if name == "if" {
+                        self.evaluate_condition(rest).map_err(|e| format!("{error_prefix}: {e}"))?
+                    } else {
+                        let Some(PpToken { kind: PpTokenKind::Identifier, text, .. }) = rest.first() else {
+                            return Err(format!("{error_prefix}: `#{name}` expects a macro name"));
+                        };
+                        self.macros.contains_key(text) == (name == "ifdef")
+                    };
+                    conditionals.push(Conditional { active: condition, taken: condition, parent_active: active, seen_else: false });
+                },
+                "elif" | "else" => {
+                    let Some(conditional) = conditionals.last_mut() else {
+                        return Err(format!("{error_prefix}: `#{name}` without `#if`"));
+                    };
+                    if conditional.seen_else {
+                        return Err(format!("{error_prefix}: `#{name}` after `#else`"));
+                    }
+                    let condition = if !conditional.parent_active || conditional.taken {
+                        false
+                    } else if name == "elif" {
+                        self.evaluate_condition(rest).map_err(|e| format!("{error_prefix}: {e}"))?
+                    } else {
+                        true
+                    };
+                    let conditional = conditionals.last_mut().unwrap();
+                    conditional.active = condition;
+                    conditional.taken |= condition;
+                    conditional.seen_else = name == "else";
+                },
+                "endif" => {
+                    if conditionals.pop().is_none() {
+                        return Err(format!("{error_prefix}: `#endif` without `#if`"));
+                    }
+                },
+                _ if !active => {},
+                "" => {},
+                "define" => self.define(rest).map_err(|e| format!("{error_prefix}: {e}"))?,
+                "undef" => {
+                    let Some(PpToken { kind: PpTokenKind::Identifier, text, .. }) = rest.first() else {
+                        return Err(format!("{error_prefix}: `#undef` expects a macro name"));
+                    };
+                    self.macros.remove(text);
+                },
+                "include" => {
+                    let included = self.resolve_include(directive.trim_start()[name.len()..].trim(), rest, path)
+                        .map_err(|e| format!("{error_prefix}: {e}"))?;
+                    if !self.once_files.contains(&included.canonicalize().unwrap_or(included.clone())) {
+                        self.process_file(&included, output)?;
+                        output.push_str(&self.line_marker(line_number + 1));
+                        continue;
+                    }
+                },
+                // `gcc -E` writes `# <line> "<file>" <flags>...` markers, which take no macros and ignore the flags.
+                _ if name == "line" || name.bytes().all(|byte| byte.is_ascii_digit()) => {
+                    let rest = match name {
+                        "line" => self.expand(rest).map_err(|e| format!("{}:{e}", self.presumed_file))?,
+                        _ => tokens,
+                    };
+                    let Some(Ok(presumed_line)) = rest.first().map(|token| token.text.parse::<i64>()) else {
+                        return Err(format!("{error_prefix}: `#line` expects a line number"));
+                    };
+                    // The line after the directive gets the given number.
+                    line_delta = presumed_line - (index as i64 + 2);
+                    if let Some(file) = rest.get(1).filter(|token| token.kind == PpTokenKind::StringLiteral) {
+                        self.presumed_file = parse_string_literal(&file.text[1..file.text.len() - 1]);
+                    }
+                    output.push_str(&self.line_marker(presumed_line as u32));
+                    continue;
+                },
+                "error" => {
+                    return Err(format!("{error_prefix}: #error {}", spell(&rest)));
+                },
+                "pragma" => {
+                    if rest.first().is_some_and(|token| token.text == "once") {
+                        self.once_files.insert(path.canonicalize().unwrap_or(path.into()));
+                    }
+                },
+                _ => return Err(format!("{error_prefix}: Unknown directive `#{name}`")),
+            }
+            output.push('\n');
+        }
+
+        if !conditionals.is_empty() {
+            return Err(format!("{}: Unterminated `#if`", self.presumed_file));
+        }
+        self.flush(&mut pending, output).map_err(|e| format!("{}:{e}", self.presumed_file))
+    }
+
+    // Tells the lexer where the next line comes from, in the same form as `gcc -E`.
+    fn line_marker(&self, line: u32) -> String {
+        format!("# {line} {}\n", string_literal(&self.presumed_file))
+    }
+
+    // Tokens outside of macro expansions keep their columns, so that positions in the output match the source.
+    fn flush(&self, pending: &mut Vec<PpToken>, output: &mut String) -> Result<(), String> {
+        let mut column = 1;
+        for token in self.expand(std::mem::take(pending))? {
+            if token.kind == PpTokenKind::Newline {
+                output.push('\n');
+                column = 1;
+                continue;
+            }
+            if token.hideset.is_empty() && token.column > column {
+                output.extend(std::iter::repeat_n(' ', token.column - column));
+                column = token.column;
+            } else if token.leading_space && column > 1 {
+                output.push(' ');
+                column += 1;
+            }
+            output.push_str(&token.text);
+            column += token.text.len();
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, tokens: Vec<PpToken>) -> Result<(), String> {
+        let mut tokens = tokens.into_iter().peekable();
+        let Some(PpToken { kind: PpTokenKind::Identifier, text: name, .. }) = tokens.next() else {
+            return Err("`#define` expects a macro name".into());
+        };
+        if name == "defined" {
+            return Err("`defined` cannot be used as a macro name".into());
+        }
+        // A function-like macro has its `(` right after the name.
+        let macro_ = if tokens.peek().is_some_and(|token| token.is("(") && !token.leading_space) {
+            tokens.next();
+            let mut params = Vec::new();
+            let mut variadic = false;
+            loop {
+                let Some(token) = tokens.next() else {
+                    return Err(format!("Unterminated parameter list of `{name}`"));
+                };
+                match token.kind {
+                    PpTokenKind::Identifier if !variadic => params.push(token.text),
+                    PpTokenKind::Punctuator if token.text == "..." && !variadic => {
+                        params.push("__VA_ARGS__".into());
+                        variadic = true;
+                    },
+                    PpTokenKind::Punctuator if token.text == ")" && params.is_empty() => break,
+                    _ => return Err(format!("Malformed parameter list of `{name}`: `{}`", token.text)),
+                }
+                match tokens.next() {
+                    Some(token) if token.is(",") && !variadic => {},
+                    Some(token) if token.is(")") => break,
+                    _ => return Err(format!("Malformed parameter list of `{name}`")),
+                }
+            }
+            Macro::Function(params, variadic, tokens.collect())
+        } else {
+            Macro::Object(tokens.collect())
+        };
+        let body = match &macro_ {
+            Macro::Object(body) | Macro::Function(_, _, body) => body,
+        };
+        if body.first().is_some_and(|token| token.is("##")) || body.last().is_some_and(|token| token.is("##")) {
+            return Err(format!("`##` cannot appear at either end of the replacement list of `{name}`"));
+        }
+        self.macros.insert(name, macro_);
+        Ok(())
+    }
+
+    fn resolve_include(&self, raw: &str, tokens: Vec<PpToken>, current: &Path) -> Result<PathBuf, String> {
+        let (name, quoted) = if let Some(rest) = raw.strip_prefix('<') {
+            let end = rest.find('>').ok_or("Missing `>` in `#include`")?;
+            (rest[..end].to_string(), false)
+        } else if let Some(rest) = raw.strip_prefix('"') {
+            let end = rest.find('"').ok_or("Missing `\"` in `#include`")?;
+            (rest[..end].to_string(), true)
+        } else {
+            // Computed include: the expansion must spell one of the two forms above.
+            let expanded = spell(&self.expand(tokens)?);
+            if !expanded.starts_with('<') && !expanded.starts_with('"') {
+                return Err(format!("`#include` expects \"FILENAME\" or <FILENAME>, found `{expanded}`"));
+            }
+            return self.resolve_include(&expanded, vec![], current);
+        };
+
+        let current_dir = current.parent().map(Path::to_path_buf).unwrap_or_default();
+        let candidates = quoted.then_some(current_dir).into_iter()
+            .chain(self.include_paths.iter().cloned())
+            .chain(SYSTEM_INCLUDE_PATHS.iter().map(PathBuf::from));
+        for dir in candidates {
+            let candidate = dir.join(&name);
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+        Err(format!("Cannot find include file `{name}`"))
+    }
+
+    fn expand(&self, tokens: Vec<PpToken>) -> Result<Vec<PpToken>, String> {
+        let mut input: VecDeque<PpToken> = tokens.into();
+        let mut output = Vec::new();
+        while let Some(token) = input.pop_front() {
+            if token.kind != PpTokenKind::Identifier || token.hideset.contains(&token.text) {
+                output.push(token);
+                continue;
+            }
+            match self.macros.get(&token.text) {
+                None => output.push(self.expand_builtin(token)),
+                Some(Macro::Object(body)) => {
+                    let mut hideset = token.hideset.clone();
+                    hideset.push(token.text.clone());
+                    let replaced = self.substitute(body, &[], &[], &token, hideset)?;
+                    prepend(&mut input, replaced);
+                },
+                Some(Macro::Function(params, variadic, body)) => {
+                    // Without a following `(` the name isn't an invocation.
+                    let Some(open) = input.iter().position(|next| next.kind != PpTokenKind::Newline) else {
+                        output.push(token);
+                        continue;
+                    };
+                    if !input[open].is("(") {
+                        output.push(token);
+                        continue;
+                    }
+                    let (args, close, newlines) = collect_args(&mut input, &token)?;
+                    let args = match_args(args, params, *variadic, &token)?;
+                    // Only names hidden at both ends of the invocation stay hidden.
+                    let mut hideset: Vec<String> = token.hideset.iter().filter(|name| close.hideset.contains(name)).cloned().collect();
+                    hideset.push(token.text.clone());
+                    let mut replaced = self.substitute(body, params, &args, &token, hideset)?;
+                    // Keep the line count when the invocation spanned several lines.
+                    replaced.extend(newlines);
+                    prepend(&mut input, replaced);
+                },
+            }
+        }
+        Ok(output)
+    }
+
+    fn expand_builtin(&self, token: PpToken) -> PpToken {
+        match token.text.as_str() {
+            "__LINE__" => PpToken { kind: PpTokenKind::Number, text: token.line.to_string(), ..token },
+            "__FILE__" => PpToken { kind: PpTokenKind::StringLiteral, text: string_literal(&self.presumed_file), ..token },
+            _ => token,
+        }
+    }
+
+    // Replaces the parameters in `body`, applies `#` and `##`, and adds `hideset` to the hide set of the result.
+    fn substitute(&self, body: &[PpToken], params: &[String], args: &[Vec<PpToken>], invocation: &PpToken, hideset: Vec<String>) -> Result<Vec<PpToken>, String> {
+        let param_index = |token: &PpToken| {
+            (token.kind == PpTokenKind::Identifier).then(|| params.iter().position(|param| *param == token.text)).flatten()
+        };
+        let is_function_like = !params.is_empty() || !args.is_empty();
+
+        let mut output: Vec<PpToken> = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            let token = &body[i];
+            if token.is("#") && is_function_like {
+                let Some(index) = body.get(i + 1).and_then(param_index) else {
+                    return Err(format!("{}: `#` is not followed by a macro parameter in `{}`", invocation.line, invocation.text));
+                };
+                output.push(PpToken::new(PpTokenKind::StringLiteral, &stringify(&args[index]), token.leading_space, invocation.line));
+                i += 2;
+            } else if token.is("##") {
+                let lhs = output.pop().expect("That `##` should not start a replacement list");
+                let rhs = match body.get(i + 1).and_then(param_index) {
+                    Some(index) => args[index].clone(),
+                    None => vec![body[i + 1].clone()],
+                };
+                let (first, rest) = match rhs.split_first() {
+                    Some((first, rest)) => (first.clone(), rest.to_vec()),
+                    None => (PpToken::new(PpTokenKind::Placemarker, "", false, invocation.line), vec![]),
+                };
+                output.push(paste(lhs, first)?);
+                output.extend(rest);
+                i += 2;
+            } else if let Some(index) = param_index(token) {
+                let pasted = body.get(i + 1).is_some_and(|next| next.is("##"));
+                let mut arg = if pasted { args[index].clone() } else { self.expand(args[index].clone())? };
+                match arg.first_mut() {
+                    Some(first) => first.leading_space = token.leading_space,
+                    None if pasted => arg.push(PpToken::new(PpTokenKind::Placemarker, "", token.leading_space, invocation.line)),
+                    None => {},
+                }
+                output.extend(arg);
+                i += 1;
+            } else {
+                output.push(token.clone());
+                i += 1;
+            }
+        }
+
+        let mut output: Vec<PpToken> = output.into_iter().filter(|token| token.kind != PpTokenKind::Placemarker).collect();
+        for token in output.iter_mut() {
+            token.line = invocation.line;
+            for name in &hideset {
+                if !token.hideset.contains(name) {
+                    token.hideset.push(name.clone());
+                }
+            }
+        }
+        if let Some(first) = output.first_mut() {
+            first.leading_space = invocation.leading_space;
+        }
+        Ok(output)
+    }
+
+    fn evaluate_condition(&self, tokens: Vec<PpToken>) -> Result<bool, String> {
+        // `defined` is resolved before macro expansion.
+        let mut resolved = Vec::new();
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            if token.kind != PpTokenKind::Identifier || token.text != "defined" {
+                resolved.push(token);
+                continue;
+            }
+            let mut name = tokens.next().ok_or("`defined` expects a macro name")?;
+            if name.is("(") {
+                name = tokens.next().ok_or("`defined` expects a macro name")?;
+                if !tokens.next().is_some_and(|token| token.is(")")) {
+                    return Err("Missing `)` after `defined(`".into());
+                }
+            }
+            if name.kind != PpTokenKind::Identifier {
+                return Err(format!("`defined` expects a macro name, found `{}`", name.text));
+            }
+            let value = if self.macros.contains_key(&name.text) { "1" } else { "0" };
+            resolved.push(PpToken::new(PpTokenKind::Number, value, token.leading_space, token.line));
+        }
+
+        let expanded: Vec<PpToken> = self.expand(resolved)?.into_iter()
+            .filter(|token| token.kind != PpTokenKind::Newline)
+            .collect();
+        if expanded.is_empty() {
+            return Err("`#if` expects an expression".into());
+        }
+        let mut parser = ConditionParser { tokens: &expanded, pos: 0, evaluated: true };
+        let value = parser.parse_conditional()?;
+        if let Some(token) = expanded.get(parser.pos) {
+            return Err(format!("Unexpected `{}` in `#if` expression", token.text));
+        }
+        Ok(value.is_true())
+    }
+}
+
+fn prepend(input: &mut VecDeque<PpToken>, tokens: Vec<PpToken>) {
+    for token in tokens.into_iter().rev() {
+        input.push_front(token);
+    }
+}
+
+// Collects the comma-separated arguments of an invocation whose `(` is next in `input`, along with the closing `)`;
+// newlines inside the invocation are returned separately.
+fn collect_args(input: &mut VecDeque<PpToken>, invocation: &PpToken) -> Result<(Vec<Arg>, PpToken, Vec<PpToken>), String> {
+    let mut newlines = Vec::new();
+    let mut args = vec![Vec::new()];
+    let mut depth = 0;
+    let mut pending_space = false;
+    loop {
+        let Some(mut token) = input.pop_front() else {
+            return Err(format!("{}: Unterminated invocation of macro `{}`", invocation.line, invocation.text));
+        };
+        if token.kind == PpTokenKind::Newline {
+            newlines.push(token);
+            pending_space = true;
+            continue;
+        }
+        token.leading_space |= pending_space;
+        pending_space = false;
+        if token.is("(") {
+            depth += 1;
+            if depth == 1 {
+                continue;
+            }
+        } else if token.is(")") {
+            depth -= 1;
+            if depth == 0 {
+                return Ok((args, token, newlines));
+            }
+        } else if token.is(",") && depth == 1 {
+            args.push(Vec::new());
+            continue;
+        }
+        args.last_mut().unwrap().push(token);
+    }
+}
+
+fn match_args(mut args: Vec<Arg>, params: &[String], variadic: bool, invocation: &PpToken) -> Result<Vec<Arg>, String> {
+    // `F()` passes one empty argument, which is no argument at all for a macro without parameters.
+    if params.is_empty() && args.len() == 1 && args[0].is_empty() {
+        args.clear();
+    }
+    if variadic && args.len() >= params.len() {
+        let mut variadic_args = args.split_off(params.len() - 1);
+        let mut joined = variadic_args.remove(0);
+        for arg in variadic_args {
+            joined.push(PpToken::new(PpTokenKind::Punctuator, ",", false, 0));
+            joined.extend(arg);
+        }
+        args.push(joined);
+    } else if variadic && args.len() + 1 == params.len() {
+        args.push(Vec::new());
+    }
+    if args.len() != params.len() {
+        return Err(format!("{}: Macro `{}` expects {} arguments, but {} were given", invocation.line, invocation.text, params.len(), args.len()));
+    }
+    Ok(args)
+}
+
+fn stringify(tokens: &[PpToken]) -> String {
+    let mut string = String::from("\"");
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && token.leading_space {
+            string.push(' ');
+        }
+        if matches!(token.kind, PpTokenKind::StringLiteral | PpTokenKind::CharConstant) {
+            string.push_str(&token.text.replace('\\', "\\\\").replace('"', "\\\""));
+        } else {
+            string.push_str(&token.text);
+        }
+    }
+    string.push('"');
+    string
+}
+
+fn paste(lhs: PpToken, rhs: PpToken) -> Result<PpToken, String> {
+    if lhs.kind == PpTokenKind::Placemarker {
+        return Ok(PpToken { leading_space: lhs.leading_space, ..rhs });
+    }
+    if rhs.kind == PpTokenKind::Placemarker {
+        return Ok(lhs);
+    }
+    let text = format!("{}{}", lhs.text, rhs.text);
+    let mut tokens = tokenize(&text, lhs.line);
+    if tokens.len() != 1 {
+        return Err(format!("{}: Pasting `{}` and `{}` does not give a valid preprocessing token", lhs.line, lhs.text, rhs.text));
+    }
+    let mut token = tokens.remove(0);
+    token.leading_space = lhs.leading_space;
+    token.hideset = lhs.hideset;
+    Ok(token)
+}
+
+fn spell(tokens: &[PpToken]) -> String {
+    let mut spelling = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && token.leading_space {
+            spelling.push(' ');
+        }
+        spelling.push_str(&token.text);
+    }
+    spelling
+}
+
+/// `string` as a C string literal: quotes and backslashes are escaped, and bytes other than printable ASCII written in
+/// octal, which keeps the literal within the ASCII the lexer accepts.
+pub(crate) fn string_literal(string: &str) -> String {
+    let mut literal = String::from('"');
+    for byte in string.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            },
+            b' '..=b'~' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// The string written by the body of a C string literal, without its quotes.
+pub(crate) fn parse_string_literal(body: &str) -> String {
+    let mut bytes = Vec::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            bytes.extend(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        let Some(escape) = chars.next() else { break };
+        let byte = match escape {
+            'n' => b'\n',
+            't' => b'\t',
+            'r' => b'\r',
+            'a' => 7,
+            'b' => 8,
+            'f' => 12,
+            'v' => 11,
+            '0'..='7' => {
+                let mut value = escape.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    let Some(digit) = chars.peek().and_then(|ch| ch.to_digit(8)) else { break };
+                    value = value * 8 + digit;
+                    chars.next();
+                }
+                value as u8
+            },
+            'x' => {
+                let mut value = 0u32;
+                while let Some(digit) = chars.peek().and_then(|ch| ch.to_digit(16)) {
+                    value = value.wrapping_mul(16) + digit;
+                    chars.next();
+                }
+                value as u8
+            },
+            _ => {
+                bytes.extend(escape.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            },
+        };
+        bytes.push(byte);
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// Removes comments and line splices; the newlines they swallow are re-emitted at the end of the logical line.
+fn remove_comments_and_splices(src: &str) -> String {
+    let mut output = String::with_capacity(src.len());
+    let mut swallowed_newlines = 0;
+    let mut chars = src.chars().peekable();
+    let mut quote = None;
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                swallowed_newlines += 1;
+            },
+            '\n' => {
+                output.push('\n');
+                output.extend(std::iter::repeat_n('\n', swallowed_newlines));
+                swallowed_newlines = 0;
+                quote = None;
+            },
+            '\\' if quote.is_some() => {
+                output.push(ch);
+                if let Some(escaped) = chars.next_if(|next| *next != '\n') {
+                    output.push(escaped);
+                }
+            },
+            '"' | '\'' if quote.is_none() => {
+                quote = Some(ch);
+                output.push(ch);
+            },
+            '"' | '\'' if quote == Some(ch) => {
+                quote = None;
+                output.push(ch);
+            },
+            '/' if quote.is_none() && chars.peek() == Some(&'/') => {
+                while chars.next_if(|next| *next != '\n').is_some() {}
+            },
+            '/' if quote.is_none() && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for ch in chars.by_ref() {
+                    if previous == '*' && ch == '/' {
+                        break;
+                    }
+                    if ch == '\n' {
+                        swallowed_newlines += 1;
+                    }
+                    previous = ch;
+                }
+                output.push(' ');
+            },
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+const PUNCTUATORS: [&str; 48] = [
+    "%:%:", "...", "<<=", ">>=",
+    "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=", "/=", "%=", "+=", "-=", "&=", "^=", "|=",
+    "##", "<:", ":>", "<%", "%>", "%:",
+    "[", "]", "(", ")", "{", "}", ".", "&", "*", "+", "-", "~", "!", "/", "%", "<", ">", "^", "|",
+];
+
+fn tokenize(line: &str, line_number: u32) -> Vec<PpToken> {
+    let mut tokens = Vec::new();
+    let mut rest = line;
+    let mut leading_space = false;
+    while let Some(ch) = rest.chars().next() {
+        if ch.is_whitespace() {
+            rest = rest.trim_start();
+            leading_space = true;
+            continue;
+        }
+        let (kind, len) = if ch.is_ascii_alphabetic() || ch == '_' {
+            (PpTokenKind::Identifier, rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len()))
+        } else if ch.is_ascii_digit() || (ch == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            (PpTokenKind::Number, pp_number_len(rest))
+        } else if ch == '"' || ch == '\'' {
+            let kind = if ch == '"' { PpTokenKind::StringLiteral } else { PpTokenKind::CharConstant };
+            (kind, quoted_len(rest, ch))
+        } else if let Some(punctuator) = PUNCTUATORS.iter().find(|punctuator| rest.starts_with(**punctuator)) {
+            (PpTokenKind::Punctuator, punctuator.len())
+        } else if ";,?:=#".contains(ch) {
+            (PpTokenKind::Punctuator, 1)
+        } else {
+            (PpTokenKind::Other, ch.len_utf8())
+        };
+        let column = line.len() - rest.len() + 1;
+        tokens.push(PpToken { column, ..PpToken::new(kind, &rest[..len], leading_space, line_number) });
+        rest = &rest[len..];
+        leading_space = false;
+    }
+    tokens
+}
+
+fn pp_number_len(src: &str) -> usize {
+    let bytes = src.as_bytes();
+    let mut len = 1;
+    while len < bytes.len() {
+        let ch = bytes[len];
+        let exponent_sign = matches!(ch, b'+' | b'-') && matches!(bytes[len - 1], b'e' | b'E' | b'p' | b'P');
+        if exponent_sign || ch.is_ascii_alphanumeric() || ch == b'_' || ch == b'.' {
+            len += 1;
+        } else {
+            break;
+        }
+    }
+    len
+}
+
+// An unterminated literal extends to the end of the line.
+fn quoted_len(src: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, ch) in src.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return i + 1;
+        }
+    }
+    src.len()
+}
+
+// A value of an `#if` expression: every integer there is an `intmax_t` or a `uintmax_t`.
+#[derive(Clone, Copy)]
+struct Value {
+    bits: u64,
+    unsigned: bool,
+}
+
+impl Value {
+    fn signed(value: i64) -> Self {
+        Self { bits: value as u64, unsigned: false }
+    }
+
+    fn truth(condition: bool) -> Self {
+        Self::signed(condition as i64)
+    }
+
+    fn is_true(&self) -> bool {
+        self.bits != 0
+    }
+}
+
+struct ConditionParser<'a> {
+    tokens: &'a [PpToken],
+    pos: usize,
+    // False in operands that don't affect the result, where errors like division by zero are ignored.
+    evaluated: bool,
+}
+
+impl ConditionParser<'_> {
+    fn peek(&self) -> Option<&PpToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, punctuator: &str) -> Result<(), String> {
+        match self.peek() {
+            Some(token) if token.is(punctuator) => {
+                self.pos += 1;
+                Ok(())
+            },
+            Some(token) => Err(format!("Expect `{punctuator}` in `#if` expression, found `{}`", token.text)),
+            None => Err(format!("Expect `{punctuator}` in `#if` expression")),
+        }
+    }
+
+    fn parse_conditional(&mut self) -> Result<Value, String> {
+        let condition = self.parse_binary(0)?;
+        if !self.peek().is_some_and(|token| token.is("?")) {
+            return Ok(condition);
+        }
+        self.pos += 1;
+        // Only the branch taken is evaluated, so errors in the other one don't count.
+        let then = self.parse_unevaluated_if(!condition.is_true(), Self::parse_conditional)?;
+        self.expect(":")?;
+        let otherwise = self.parse_unevaluated_if(condition.is_true(), Self::parse_conditional)?;
+        let unsigned = then.unsigned || otherwise.unsigned;
+        Ok(Value { unsigned, ..if condition.is_true() { then } else { otherwise } })
+    }
+
+    fn parse_unevaluated_if(&mut self, skip: bool, parse: impl FnOnce(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        let evaluated = self.evaluated;
+        self.evaluated &= !skip;
+        let value = parse(self);
+        self.evaluated = evaluated;
+        value
+    }
+
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Value, String> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(operator) = self.peek().filter(|token| token.kind == PpTokenKind::Punctuator).map(|token| token.text.clone()) {
+            let Some(precedence) = binary_precedence(&operator) else { break };
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            // `&&` and `||` don't evaluate their right operand once the left one decides the result.
+            let decided = match operator.as_str() {
+                "&&" => !lhs.is_true(),
+                "||" => lhs.is_true(),
+                _ => false,
+            };
+            let rhs = self.parse_unevaluated_if(decided, |parser| parser.parse_binary(precedence + 1))?;
+            lhs = match operator.as_str() {
+                "&&" => Value::truth(lhs.is_true() && rhs.is_true()),
+                "||" => Value::truth(lhs.is_true() || rhs.is_true()),
+                // The result of a shift has the type of its left operand.
+                "<<" => Value { bits: lhs.bits.wrapping_shl(rhs.bits as u32), ..lhs },
+                ">>" if lhs.unsigned => Value { bits: lhs.bits.wrapping_shr(rhs.bits as u32), ..lhs },
+                ">>" => Value::signed((lhs.bits as i64).wrapping_shr(rhs.bits as u32)),
+                "/" | "%" if rhs.bits == 0 && !self.evaluated => Value::signed(0),
+                "/" | "%" if rhs.bits == 0 => return Err("Division by zero in `#if` expression".into()),
+                _ if lhs.unsigned || rhs.unsigned => unsigned_binary(&operator, lhs.bits, rhs.bits),
+                _ => signed_binary(&operator, lhs.bits as i64, rhs.bits as i64),
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Value, String> {
+        let token = self.peek().ok_or("Incomplete `#if` expression")?.clone();
+        self.pos += 1;
+        match token.kind {
+            PpTokenKind::Punctuator => match token.text.as_str() {
+                "+" => self.parse_unary(),
+                "-" => self.parse_unary().map(|value| Value { bits: value.bits.wrapping_neg(), ..value }),
+                "~" => self.parse_unary().map(|value| Value { bits: !value.bits, ..value }),
+                "!" => Ok(Value::truth(!self.parse_unary()?.is_true())),
+                "(" => {
+                    let value = self.parse_conditional()?;
+                    self.expect(")")?;
+                    Ok(value)
+                },
+                _ => Err(format!("Unexpected `{}` in `#if` expression", token.text)),
+            },
+            PpTokenKind::Number => parse_integer(&token.text),
+            PpTokenKind::CharConstant => parse_char_constant(&token.text).map(Value::signed),
+            // Identifiers left after macro expansion evaluate to 0.
+            PpTokenKind::Identifier => Ok(Value::signed(0)),
+            _ => Err(format!("Unexpected `{}` in `#if` expression", token.text)),
+        }
+    }
+}
+
+// `intmax_t` and `uintmax_t` arithmetic, with the usual arithmetic conversions of the operands.
+fn signed_binary(operator: &str, lhs: i64, rhs: i64) -> Value {
+    match operator {
+        "*" => Value::signed(lhs.wrapping_mul(rhs)),
+        "/" => Value::signed(lhs.wrapping_div(rhs)),
+        "%" => Value::signed(lhs.wrapping_rem(rhs)),
+        "+" => Value::signed(lhs.wrapping_add(rhs)),
+        "-" => Value::signed(lhs.wrapping_sub(rhs)),
+        "<" => Value::truth(lhs < rhs),
+        ">" => Value::truth(lhs > rhs),
+        "<=" => Value::truth(lhs <= rhs),
+        ">=" => Value::truth(lhs >= rhs),
+        "==" => Value::truth(lhs == rhs),
+        "!=" => Value::truth(lhs != rhs),
+        "&" => Value::signed(lhs & rhs),
+        "^" => Value::signed(lhs ^ rhs),
+        "|" => Value::signed(lhs | rhs),
+        _ => unreachable!("Operators without precedence should stop the loop"),
+    }
+}
+
+fn unsigned_binary(operator: &str, lhs: u64, rhs: u64) -> Value {
+    let unsigned = |bits| Value { bits, unsigned: true };
+    match operator {
+        "*" => unsigned(lhs.wrapping_mul(rhs)),
+        "/" => unsigned(lhs / rhs),
+        "%" => unsigned(lhs % rhs),
+        "+" => unsigned(lhs.wrapping_add(rhs)),
+        "-" => unsigned(lhs.wrapping_sub(rhs)),
+        "<" => Value::truth(lhs < rhs),
+        ">" => Value::truth(lhs > rhs),
+        "<=" => Value::truth(lhs <= rhs),
+        ">=" => Value::truth(lhs >= rhs),
+        "==" => Value::truth(lhs == rhs),
+        "!=" => Value::truth(lhs != rhs),
+        "&" => unsigned(lhs & rhs),
+        "^" => unsigned(lhs ^ rhs),
+        "|" => unsigned(lhs | rhs),
+        _ => unreachable!("Operators without precedence should stop the loop"),
+    }
+}
+
+fn binary_precedence(operator: &str) -> Option<u8> {
+    match operator {
+        "||" => Some(1),
+        "&&" => Some(2),
+        "|" => Some(3),
+        "^" => Some(4),
+        "&" => Some(5),
+        "==" | "!=" => Some(6),
+        "<" | ">" | "<=" | ">=" => Some(7),
+        "<<" | ">>" => Some(8),
+        "+" | "-" => Some(9),
+        "*" | "/" | "%" => Some(10),
+        _ => None,
+    }
+}
+
+// Integers with a `u` suffix, or too large for `intmax_t`, are unsigned.
+fn parse_integer(text: &str) -> Result<Value, String> {
+    let digits = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    let unsigned = text[digits.len()..].contains(['u', 'U']);
+    let parsed = if let Some(hex) = digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(binary) = digits.strip_prefix("0b").or(digits.strip_prefix("0B")) {
+        u64::from_str_radix(binary, 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse::<u64>()
+    };
+    parsed.map(|bits| Value { bits, unsigned: unsigned || bits > i64::MAX as u64 })
+        .map_err(|_| format!("Invalid integer `{text}` in `#if` expression"))
+}
+
+fn parse_char_constant(text: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid character constant `{text}` in `#if` expression");
+    let inner = text.strip_prefix('\'').and_then(|text| text.strip_suffix('\'')).ok_or_else(invalid)?;
+    let Some(escape) = inner.strip_prefix('\\') else {
+        let mut chars = inner.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(ch as i64),
+            _ => Err(invalid()),
+        };
+    };
+    let value = match escape {
+        "n" => 10,
+        "t" => 9,
+        "r" => 13,
+        "a" => 7,
+        "b" => 8,
+        "f" => 12,
+        "v" => 11,
+        "\\" | "'" | "\"" | "?" => escape.as_bytes()[0] as i64,
+        _ => match escape.strip_prefix('x') {
+            Some(hex) => i64::from_str_radix(hex, 16).map_err(|_| invalid())?,
+            None => i64::from_str_radix(escape, 8).map_err(|_| invalid())?,
+        },
+    };
+    Ok(value)
+}
@@ -0,0 +1,378 @@
+//! # Graph-coloring register allocator
+//!
+//! A Chaitin–Briggs style allocator working on one asm function at a time:
+//! 1. Build an interference graph from liveness over the asm instructions;
+//! 2. Coalesce `mov`s between non-interfering operands (Briggs test for two pseudos, George test when one side is a
+//!    hard register) and rebuild until nothing more can be coalesced;
+//! 3. Simplify and select with the allocatable registers, picking spill candidates by use count over degree;
+//! 4. Rewrite pseudos to their registers, leaving the actual spills for `assign_pseudo_registers_to_stack`.
+//!
+//! The only register class is the general-purpose one, since every value is an `int`. `R10` is never allocated
+//! because the fix-up passes use it as a scratch register.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::ast_nodes::*;
+use crate::span::Span;
+
+// Caller-saved registers come first so that callee-saved ones are only used (and saved) under pressure.
+const ALLOCATABLE_REGISTERS: [AsmReg; 13] = [
+    asm::AX, asm::CX, asm::DX, asm::SI, asm::DI, asm::R8, asm::R9, asm::R11,
+    asm::BX, asm::R12, asm::R13, asm::R14, asm::R15,
+];
+
+const CALLEE_SAVED_REGISTERS: [AsmReg; 5] = [asm::BX, asm::R12, asm::R13, asm::R14, asm::R15];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Node {
+    Pseudo(String),
+    Hard(AsmReg),
+}
+
+// Ordered by node, so that simplification picks the same nodes, and the output is the same, from one run to the next.
+#[derive(Default)]
+struct InterferenceGraph {
+    neighbors: BTreeMap<Node, HashSet<Node>>,
+}
+
+impl InterferenceGraph {
+    fn add_node(&mut self, node: Node) {
+        self.neighbors.entry(node).or_default();
+    }
+
+    fn add_edge(&mut self, a: &Node, b: &Node) {
+        if a == b {
+            return;
+        }
+        self.neighbors.entry(a.clone()).or_default().insert(b.clone());
+        self.neighbors.entry(b.clone()).or_default().insert(a.clone());
+    }
+
+    fn interferes(&self, a: &Node, b: &Node) -> bool {
+        self.neighbors.get(a).is_some_and(|neighbors| neighbors.contains(b))
+    }
+
+    fn degree(&self, node: &Node) -> usize {
+        self.neighbors.get(node).map_or(0, HashSet::len)
+    }
+
+    fn remove_node(&mut self, node: &Node) {
+        if let Some(neighbors) = self.neighbors.remove(node) {
+            for neighbor in neighbors {
+                if let Some(set) = self.neighbors.get_mut(&neighbor) {
+                    set.remove(node);
+                }
+            }
+        }
+    }
+}
+
+pub fn allocate_registers(asm_program: &mut AsmProgram) {
+    let asm::Program(asm::Function(_, instructions, _, span)) = asm_program;
+
+    while let Some((from, into)) = find_coalescable_move(instructions, &build_interference_graph(instructions)) {
+        rename_pseudo(instructions, &from, &into);
+    }
+
+    let graph = build_interference_graph(instructions);
+    let coloring = color_graph(graph, &spill_costs(instructions));
+    for instruction in instructions.iter_mut() {
+        for operand in operands_mut(instruction) {
+            if let asm::Pseudo(asm::Identifier(name)) = operand {
+                if let Some(&reg) = coloring.get(name.as_str()) {
+                    *operand = asm::Register(reg);
+                }
+            }
+        }
+    }
+    instructions.retain(|instruction| !matches!(instruction, asm::Mov(src, dst, _) if src == dst));
+
+    save_callee_saved_registers(instructions, &coloring, span);
+}
+
+fn node_of(operand: &AsmOperand) -> Option<Node> {
+    match operand {
+        asm::Pseudo(asm::Identifier(name)) => Some(Node::Pseudo(name.clone())),
+        asm::Register(reg) if ALLOCATABLE_REGISTERS.contains(reg) => Some(Node::Hard(*reg)),
+        _ => None,
+    }
+}
+
+fn uses_and_defs(instruction: &AsmInstruction) -> (Vec<Node>, Vec<Node>) {
+    match instruction {
+        asm::Mov(src, dst, _) => (node_of(src).into_iter().collect(), node_of(dst).into_iter().collect()),
+        asm::Unary(_, dst, _) => (node_of(dst).into_iter().collect(), node_of(dst).into_iter().collect()),
+        asm::Binary(_, src, dst, _) => (node_of(src).into_iter().chain(node_of(dst)).collect(), node_of(dst).into_iter().collect()),
+        asm::Ret(_) => (vec![Node::Hard(asm::AX)], vec![]),
+        asm::AllocateStack(..) | asm::Push(..) | asm::Pop(..) => (vec![], vec![]),
+    }
+}
+
+// Asm function bodies are straight-line code, so a single backward sweep computes liveness.
+fn build_interference_graph(instructions: &[AsmInstruction]) -> InterferenceGraph {
+    let mut graph = InterferenceGraph::default();
+    for (i, a) in ALLOCATABLE_REGISTERS.iter().enumerate() {
+        for b in &ALLOCATABLE_REGISTERS[i + 1..] {
+            graph.add_edge(&Node::Hard(*a), &Node::Hard(*b));
+        }
+    }
+
+    let mut live: HashSet<Node> = HashSet::new();
+    for instruction in instructions.iter().rev() {
+        let (uses, defs) = uses_and_defs(instruction);
+        for def in &defs {
+            graph.add_node(def.clone());
+            for live_node in &live {
+                // The source of a `mov` doesn't interfere with its destination: they hold the same value.
+                let is_move_source = matches!(instruction, asm::Mov(src, ..) if node_of(src).as_ref() == Some(live_node));
+                if !is_move_source {
+                    graph.add_edge(def, live_node);
+                }
+            }
+        }
+        for def in &defs {
+            live.remove(def);
+        }
+        for use_ in uses {
+            graph.add_node(use_.clone());
+            live.insert(use_);
+        }
+    }
+    graph
+}
+
+fn find_coalescable_move(instructions: &[AsmInstruction], graph: &InterferenceGraph) -> Option<(String, Node)> {
+    for instruction in instructions {
+        let asm::Mov(src, dst, _) = instruction else { continue };
+        let (Some(src), Some(dst)) = (node_of(src), node_of(dst)) else { continue };
+        if src == dst || graph.interferes(&src, &dst) {
+            continue;
+        }
+        match (&src, &dst) {
+            (Node::Pseudo(a), Node::Pseudo(_)) => {
+                if briggs_test(graph, &src, &dst) {
+                    return Some((a.clone(), dst));
+                }
+            },
+            (Node::Pseudo(a), Node::Hard(_)) => {
+                if george_test(graph, &src, &dst) {
+                    return Some((a.clone(), dst));
+                }
+            },
+            (Node::Hard(_), Node::Pseudo(b)) => {
+                if george_test(graph, &dst, &src) {
+                    return Some((b.clone(), src));
+                }
+            },
+            (Node::Hard(_), Node::Hard(_)) => {},
+        }
+    }
+    None
+}
+
+// Merging is safe if the merged node has fewer than K neighbors of significant degree.
+fn briggs_test(graph: &InterferenceGraph, a: &Node, b: &Node) -> bool {
+    let k = ALLOCATABLE_REGISTERS.len();
+    let mut neighbors: HashSet<&Node> = HashSet::new();
+    neighbors.extend(graph.neighbors.get(a).into_iter().flatten());
+    neighbors.extend(graph.neighbors.get(b).into_iter().flatten());
+    let significant = neighbors.iter()
+        .filter(|neighbor| {
+            let mut degree = graph.degree(neighbor);
+            if graph.interferes(neighbor, a) && graph.interferes(neighbor, b) {
+                degree -= 1;
+            }
+            degree >= k
+        })
+        .count();
+    significant < k
+}
+
+// Merging a pseudo into a hard register is safe if each neighbor of the pseudo already interferes with the register
+// or has insignificant degree.
+fn george_test(graph: &InterferenceGraph, pseudo: &Node, hard: &Node) -> bool {
+    let k = ALLOCATABLE_REGISTERS.len();
+    graph.neighbors.get(pseudo).into_iter().flatten()
+        .all(|neighbor| graph.interferes(neighbor, hard) || graph.degree(neighbor) < k)
+}
+
+fn rename_pseudo(instructions: &mut [AsmInstruction], from: &str, into: &Node) {
+    let replacement = match into {
+        Node::Pseudo(name) => asm::Pseudo(asm::Identifier(name.clone())),
+        Node::Hard(reg) => asm::Register(*reg),
+    };
+    for instruction in instructions.iter_mut() {
+        for operand in operands_mut(instruction) {
+            if matches!(operand, asm::Pseudo(asm::Identifier(name)) if name == from) {
+                *operand = replacement.clone();
+            }
+        }
+    }
+}
+
+fn operands_mut(instruction: &mut AsmInstruction) -> Vec<&mut AsmOperand> {
+    match instruction {
+        asm::Mov(src, dst, _) => vec![src, dst],
+        asm::Unary(_, dst, _) => vec![dst],
+        asm::Binary(_, src, dst, _) => vec![src, dst],
+        asm::AllocateStack(..) | asm::Push(..) | asm::Pop(..) | asm::Ret(_) => vec![],
+    }
+}
+
+fn spill_costs(instructions: &[AsmInstruction]) -> HashMap<String, usize> {
+    let mut costs = HashMap::new();
+    for instruction in instructions {
+        let (uses, defs) = uses_and_defs(instruction);
+        for node in uses.into_iter().chain(defs) {
+            if let Node::Pseudo(name) = node {
+                *costs.entry(name).or_default() += 1;
+            }
+        }
+    }
+    costs
+}
+
+fn color_graph(mut graph: InterferenceGraph, spill_costs: &HashMap<String, usize>) -> HashMap<String, AsmReg> {
+    let k = ALLOCATABLE_REGISTERS.len();
+    let original = InterferenceGraph { neighbors: graph.neighbors.clone() };
+
+    // Simplify: hard registers stay in the graph since they are precolored.
+    let mut stack = Vec::new();
+    loop {
+        let pseudos = graph.neighbors.keys().filter(|node| matches!(node, Node::Pseudo(_)));
+        let Some(node) = pseudos.clone().find(|node| graph.degree(node) < k).cloned().or_else(|| {
+            // No trivially colorable node left: optimistically push the cheapest spill candidate.
+            pseudos
+                .min_by(|a, b| {
+                    let cost = |node: &Node| {
+                        let Node::Pseudo(name) = node else { unreachable!() };
+                        spill_costs.get(name).copied().unwrap_or(0) as f64 / graph.degree(node).max(1) as f64
+                    };
+                    cost(a).total_cmp(&cost(b))
+                })
+                .cloned()
+        }) else {
+            break;
+        };
+        graph.remove_node(&node);
+        stack.push(node);
+    }
+
+    // Select: pseudos left uncolored are the actual spills.
+    let mut coloring = HashMap::new();
+    while let Some(node) = stack.pop() {
+        let Node::Pseudo(name) = &node else { unreachable!() };
+        let taken: HashSet<AsmReg> = original.neighbors[&node].iter()
+            .filter_map(|neighbor| match neighbor {
+                Node::Hard(reg) => Some(*reg),
+                Node::Pseudo(neighbor) => coloring.get(neighbor).copied(),
+            })
+            .collect();
+        if let Some(reg) = ALLOCATABLE_REGISTERS.iter().find(|reg| !taken.contains(reg)) {
+            coloring.insert(name.clone(), *reg);
+        }
+    }
+    coloring
+}
+
+// The pushes belong to the prologue, at the function's span, and the pops to the `ret` they precede.
+fn save_callee_saved_registers(instructions: &mut Vec<AsmInstruction>, coloring: &HashMap<String, AsmReg>, span: &Span) {
+    let used: Vec<AsmReg> = CALLEE_SAVED_REGISTERS.into_iter()
+        .filter(|reg| coloring.values().any(|used| used == reg))
+        .collect();
+    if used.is_empty() {
+        return;
+    }
+
+    let mut saved = Vec::with_capacity(instructions.len() + used.len() * 2);
+    saved.extend(used.iter().map(|reg| asm::Push(*reg, span.clone())));
+    for instruction in instructions.drain(..) {
+        if let asm::Ret(span) = &instruction {
+            saved.extend(used.iter().rev().map(|reg| asm::Pop(*reg, span.clone())));
+        }
+        saved.push(instruction);
+    }
+    *instructions = saved;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::FrameLayout;
+    use crate::span::Span;
+
+    fn pseudo(name: &str) -> AsmOperand {
+        asm::Pseudo(asm::Identifier(name.to_string()))
+    }
+
+    fn allocate(instructions: Vec<AsmInstruction>) -> Vec<AsmInstruction> {
+        let mut program = asm::Program(asm::Function(asm::Identifier("main".to_string()), instructions, FrameLayout::default(), Span::default()));
+        allocate_registers(&mut program);
+        let asm::Program(asm::Function(_, instructions, ..)) = program;
+        instructions
+    }
+
+    // Sixteen values live at once: more than the thirteen allocatable registers.
+    fn pressure() -> Vec<AsmInstruction> {
+        let names: Vec<String> = (0..16).map(|i| format!("t{i}")).collect();
+        let mut instructions: Vec<AsmInstruction> = names.iter()
+            .enumerate()
+            .map(|(i, name)| asm::Mov(asm::Imm(i as u32), pseudo(name), Span::default()))
+            .collect();
+        for name in &names {
+            instructions.push(asm::Unary(asm::Neg, pseudo(name), Span::default()));
+        }
+        instructions.extend([asm::Mov(pseudo("t0"), asm::Register(asm::AX), Span::default()), asm::Ret(Span::default())]);
+        instructions
+    }
+
+    #[test]
+    fn coalesces_a_chain_of_moves() {
+        let instructions = allocate(vec![
+            asm::Mov(asm::Imm(5), pseudo("a"), Span::default()),
+            asm::Unary(asm::Neg, pseudo("a"), Span::default()),
+            asm::Mov(pseudo("a"), pseudo("b"), Span::default()),
+            asm::Unary(asm::Not, pseudo("b"), Span::default()),
+            asm::Mov(pseudo("b"), asm::Register(asm::AX), Span::default()),
+            asm::Ret(Span::default()),
+        ]);
+        let instructions: Vec<String> = instructions.iter().map(ToString::to_string).collect();
+        assert_eq!(instructions, ["mov $5, %ax", "neg %ax", "not %ax", "ret"]);
+    }
+
+    #[test]
+    fn spills_and_saves_callee_saved_registers_under_pressure() {
+        let instructions = allocate(pressure());
+
+        let destinations: Vec<&AsmOperand> = instructions.iter()
+            .filter_map(|instruction| match instruction {
+                asm::Mov(asm::Imm(_), dst, _) => Some(dst),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(destinations.len(), 16);
+        assert!(destinations.iter().any(|dst| matches!(dst, asm::Pseudo(_))), "expected spills: {destinations:?}");
+        let registers: Vec<&AsmOperand> = destinations.iter().copied().filter(|dst| matches!(dst, asm::Register(_))).collect();
+        for (i, a) in registers.iter().enumerate() {
+            assert!(!registers[i + 1..].contains(a), "{a:?} holds two live values");
+        }
+
+        let pushes: Vec<AsmReg> = instructions.iter().take_while(|i| matches!(i, asm::Push(..)))
+            .map(|i| match i { asm::Push(reg, _) => *reg, _ => unreachable!() })
+            .collect();
+        assert_eq!(pushes, CALLEE_SAVED_REGISTERS);
+        let n = instructions.len();
+        let pops: Vec<AsmReg> = instructions[n - 6..n - 1].iter()
+            .map(|i| match i { asm::Pop(reg, _) => *reg, other => panic!("expected pop, found {other:?}") })
+            .collect();
+        assert_eq!(pops, CALLEE_SAVED_REGISTERS.into_iter().rev().collect::<Vec<_>>());
+        assert!(matches!(instructions[n - 1], asm::Ret(_)));
+    }
+
+    #[test]
+    fn allocation_is_deterministic() {
+        let first = format!("{:?}", allocate(pressure()));
+        for _ in 0..10 {
+            assert_eq!(format!("{:?}", allocate(pressure())), first);
+        }
+    }
+}
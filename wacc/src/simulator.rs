@@ -0,0 +1,308 @@
+//! # x86-64 simulator for the asm IR
+//!
+//! Runs an `AsmProgram` directly, without assembling it, and returns what `main` leaves in `eax`.
+//! - The 16 general-purpose registers, the arithmetic flags and a downward-growing stack are modeled;
+//! - The prologue and epilogue the emitter adds around each function are simulated too, so frame bugs surface;
+//! - Operands that the hardware can't encode (pseudo registers, memory-to-memory `mov`s) are reported as errors
+//!   instead of being executed, which makes this a check for the fix-up passes in `codegen.rs` as well.
+
+use std::fmt;
+
+use crate::ast_nodes::*;
+
+const STACK_SIZE: usize = 1 << 16;
+const RETURN_ADDRESS: u64 = 0xdead_beef;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Flags {
+    zero: bool,
+    sign: bool,
+    carry: bool,
+    overflow: bool,
+}
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bit = |flag: bool| if flag { 1 } else { 0 };
+        write!(f, "ZF={} SF={} CF={} OF={}", bit(self.zero), bit(self.sign), bit(self.carry), bit(self.overflow))
+    }
+}
+
+pub struct Simulation {
+    pub result: i32,
+    pub flags: Flags,
+}
+
+#[derive(Clone, Copy)]
+enum Register {
+    General(AsmReg),
+    Rbp,
+    Rsp,
+}
+
+struct Machine {
+    registers: [u64; 16],
+    flags: Flags,
+    stack: Vec<u8>,
+}
+
+impl Machine {
+    fn new() -> Self {
+        // Garbage in every register, so that reads of uninitialized registers and unrestored ones stand out.
+        let registers = std::array::from_fn(|i| 0x0101_0101_0101_0101 * (i as u64 + 1));
+        let mut machine = Self { registers, flags: Flags::default(), stack: vec![0; STACK_SIZE] };
+        // Like right after a `call`: the return address is on a 16-byte aligned stack.
+        machine.set_register(Register::Rsp, STACK_SIZE as u64);
+        machine.push(RETURN_ADDRESS).expect("That the empty stack should have room for the return address");
+        machine
+    }
+
+    fn index(register: Register) -> usize {
+        match register {
+            Register::General(reg) => match reg {
+                asm::AX => 0,
+                asm::CX => 1,
+                asm::DX => 2,
+                asm::BX => 3,
+                asm::SI => 6,
+                asm::DI => 7,
+                asm::R8 => 8,
+                asm::R9 => 9,
+                asm::R10 => 10,
+                asm::R11 => 11,
+                asm::R12 => 12,
+                asm::R13 => 13,
+                asm::R14 => 14,
+                asm::R15 => 15,
+            },
+            Register::Rsp => 4,
+            Register::Rbp => 5,
+        }
+    }
+
+    fn register(&self, register: Register) -> u64 {
+        self.registers[Self::index(register)]
+    }
+
+    fn set_register(&mut self, register: Register, value: u64) {
+        self.registers[Self::index(register)] = value;
+    }
+
+    fn address(&self, offset: i32, size: usize) -> Result<usize, String> {
+        let address = self.register(Register::Rbp).wrapping_add(offset as i64 as u64) as usize;
+        if address < self.register(Register::Rsp) as usize {
+            return Err(format!("Access to {offset}(%rbp) is below the stack pointer"));
+        }
+        if address + size > self.stack.len() {
+            return Err(format!("Access to {offset}(%rbp) is outside the stack"));
+        }
+        Ok(address)
+    }
+
+    fn load(&self, address: usize, size: usize) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..size].copy_from_slice(&self.stack[address..address + size]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn store(&mut self, address: usize, size: usize, value: u64) {
+        self.stack[address..address + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    fn push(&mut self, value: u64) -> Result<(), String> {
+        let rsp = self.register(Register::Rsp).checked_sub(8).ok_or("Stack overflow")?;
+        self.set_register(Register::Rsp, rsp);
+        self.store(rsp as usize, 8, value);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<u64, String> {
+        let rsp = self.register(Register::Rsp) as usize;
+        if rsp + 8 > self.stack.len() {
+            return Err("Stack underflow".into());
+        }
+        self.set_register(Register::Rsp, rsp as u64 + 8);
+        Ok(self.load(rsp, 8))
+    }
+
+    // Every operand in the asm IR is a longword.
+    fn read(&self, operand: &AsmOperand) -> Result<u32, String> {
+        match operand {
+            asm::Imm(integer) => Ok(*integer),
+            asm::Register(reg) => Ok(self.register(Register::General(*reg)) as u32),
+            asm::Stack(offset) => Ok(self.load(self.address(*offset, 4)?, 4) as u32),
+            asm::Pseudo(asm::Identifier(name)) => Err(format!("Pseudo register `{name}` survived codegen")),
+        }
+    }
+
+    // Writing a 32-bit register zeroes its upper half.
+    fn write(&mut self, operand: &AsmOperand, value: u32) -> Result<(), String> {
+        match operand {
+            asm::Register(reg) => self.set_register(Register::General(*reg), value as u64),
+            asm::Stack(offset) => {
+                let address = self.address(*offset, 4)?;
+                self.store(address, 4, value as u64);
+            },
+            asm::Imm(_) => return Err("Immediate used as a destination".into()),
+            asm::Pseudo(asm::Identifier(name)) => return Err(format!("Pseudo register `{name}` survived codegen")),
+        }
+        Ok(())
+    }
+
+    fn set_result_flags(&mut self, result: u32, carry: bool, overflow: bool) {
+        self.flags = Flags { zero: result == 0, sign: (result as i32) < 0, carry, overflow };
+    }
+
+    fn execute(&mut self, instructions: &[AsmInstruction]) -> Result<(), String> {
+        // Prologue added by the emitter.
+        self.push(self.register(Register::Rbp))?;
+        self.set_register(Register::Rbp, self.register(Register::Rsp));
+
+        for instruction in instructions {
+            match instruction {
+                asm::Mov(src, dst, _) => {
+                    if matches!((src, dst), (asm::Stack(_), asm::Stack(_))) {
+                        return Err(format!("Memory-to-memory instruction: {instruction:?}"));
+                    }
+                    let value = self.read(src)?;
+                    self.write(dst, value)?;
+                },
+                asm::Unary(operator, dst, _) => {
+                    let value = self.read(dst)?;
+                    let result = match operator {
+                        asm::Neg => {
+                            let result = value.wrapping_neg();
+                            self.set_result_flags(result, value != 0, value == i32::MIN as u32);
+                            result
+                        },
+                        asm::Not => !value,
+                    };
+                    self.write(dst, result)?;
+                },
+                asm::Binary(operator, src, dst, _) => {
+                    if matches!((src, dst), (asm::Stack(_), asm::Stack(_))) {
+                        return Err(format!("Memory-to-memory instruction: {instruction:?}"));
+                    }
+                    let (src_value, dst_value) = (self.read(src)?, self.read(dst)?);
+                    let result = match operator {
+                        asm::Xor => src_value ^ dst_value,
+                    };
+                    self.set_result_flags(result, false, false);
+                    self.write(dst, result)?;
+                },
+                asm::AllocateStack(bytes, _) => {
+                    let rsp = self.register(Register::Rsp).checked_sub(*bytes as u64).ok_or("Stack overflow")?;
+                    self.set_register(Register::Rsp, rsp);
+                },
+                asm::Push(reg, _) => {
+                    self.push(self.register(Register::General(*reg)))?;
+                },
+                asm::Pop(reg, _) => {
+                    let value = self.pop()?;
+                    self.set_register(Register::General(*reg), value);
+                },
+                asm::Ret(_) => {
+                    // Epilogue added by the emitter.
+                    self.set_register(Register::Rsp, self.register(Register::Rbp));
+                    let rbp = self.pop()?;
+                    self.set_register(Register::Rbp, rbp);
+                    if self.pop()? != RETURN_ADDRESS {
+                        return Err("Returned to a corrupted return address".into());
+                    }
+                    return Ok(());
+                },
+            }
+        }
+        Err("Function ended without returning".into())
+    }
+}
+
+/// Runs `main` and returns the value it leaves in `eax`, along with the final flags.
+pub fn simulate_asm_program(asm_program: &AsmProgram) -> Result<Simulation, String> {
+    let asm::Program(asm::Function(_, instructions, ..)) = asm_program;
+    let mut machine = Machine::new();
+    let callee_saved = [asm::BX, asm::R12, asm::R13, asm::R14, asm::R15].map(|reg| (reg, machine.register(Register::General(reg))));
+    machine.execute(instructions)?;
+    for (reg, value) in callee_saved {
+        if machine.register(Register::General(reg)) != value {
+            return Err(format!("Callee-saved register {reg:?} wasn't restored"));
+        }
+    }
+    Ok(Simulation { result: machine.register(Register::General(asm::AX)) as i32, flags: machine.flags })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::{assign_pseudo_registers_to_stack, fix_invalid_mov_instructions, gen_asm_program};
+    use crate::frame::FrameLayout;
+    use crate::span::Span;
+
+    // `main` returning 7 through `a` and `b`, as codegen leaves it before register allocation.
+    fn copies_through_pseudos() -> AsmProgram {
+        let span = Span::default();
+        let pseudo = |name: &str| asm::Pseudo(asm::Identifier(name.into()));
+        let instructions = vec![
+            asm::Mov(asm::Imm(7), pseudo("a"), span.clone()),
+            asm::Mov(pseudo("a"), pseudo("b"), span.clone()),
+            asm::Mov(pseudo("b"), asm::Register(asm::AX), span.clone()),
+            asm::Ret(span.clone()),
+        ];
+        asm::Program(asm::Function(asm::Identifier("main".into()), instructions, FrameLayout::default(), span))
+    }
+
+    fn simulation_error(asm_program: &AsmProgram) -> String {
+        match simulate_asm_program(asm_program) {
+            Ok(simulation) => panic!("the simulation unexpectedly returned {}", simulation.result),
+            Err(e) => e,
+        }
+    }
+
+    // Each pass is simulated on its own: its input has to fail the way the pass is there to fix, and its output has to run.
+    #[test]
+    fn assign_pseudo_registers_to_stack_pass() {
+        let mut asm_program = copies_through_pseudos();
+        assert!(simulation_error(&asm_program).contains("Pseudo register `a` survived codegen"));
+        assign_pseudo_registers_to_stack(&mut asm_program);
+        let asm::Program(asm::Function(_, instructions, ..)) = &asm_program;
+        assert!(matches!(instructions[2], asm::Mov(asm::Stack(-4), asm::Stack(-8), _)), "{instructions:?}");
+        assert!(simulation_error(&asm_program).contains("Memory-to-memory instruction"));
+    }
+
+    #[test]
+    fn fix_invalid_mov_instructions_pass() {
+        let mut asm_program = copies_through_pseudos();
+        assign_pseudo_registers_to_stack(&mut asm_program);
+        fix_invalid_mov_instructions(&mut asm_program);
+        let asm::Program(asm::Function(_, instructions, ..)) = &asm_program;
+        assert!(matches!(instructions[2], asm::Mov(asm::Stack(-4), asm::Register(asm::R10), _)), "{instructions:?}");
+        assert!(matches!(instructions[3], asm::Mov(asm::Register(asm::R10), asm::Stack(-8), _)), "{instructions:?}");
+        assert_eq!(simulate_asm_program(&asm_program).unwrap().result, 7);
+
+        // Movs the hardware can encode are left alone.
+        let before = format!("{asm_program:?}");
+        fix_invalid_mov_instructions(&mut asm_program);
+        assert_eq!(format!("{asm_program:?}"), before);
+    }
+
+    // Sixteen values live at once push the allocator into the callee-saved registers and into spills; the simulation
+    // fails if any of those registers isn't restored.
+    #[test]
+    fn register_pressure_restores_callee_saved_registers() {
+        let span = Span::default();
+        let variable = |name: String| tacky::Variable(tacky::Identifier(name));
+        let mut instructions: Vec<TackyInstruction> = (0..16)
+            .map(|i| tacky::Unary(tacky::Negate, tacky::Constant(i), variable(format!("t{i}")), span.clone()))
+            .collect();
+        for i in 0..16 {
+            instructions.push(tacky::Unary(tacky::Negate, variable(format!("t{i}")), variable("sum".into()), span.clone()));
+        }
+        instructions.push(tacky::Return(variable("sum".into()), span.clone()));
+        let asm_program = gen_asm_program(tacky::Program(tacky::Function(tacky::Identifier("main".into()), instructions, span)));
+
+        let asm::Program(asm::Function(_, asm_instructions, ..)) = &asm_program;
+        assert!(asm_instructions.iter().any(|instruction| matches!(instruction, asm::Push(asm::BX, _))), "{asm_instructions:?}");
+        assert!(asm_instructions.iter().any(|instruction| matches!(instruction, asm::Mov(_, asm::Stack(_), _))), "{asm_instructions:?}");
+        assert_eq!(simulate_asm_program(&asm_program).unwrap().result, 15);
+    }
+}
@@ -0,0 +1,29 @@
+//! # Source locations
+//!
+//! A `Span` points at a range of the preprocessed source. Its `line` and `column` are the presumed ones: the lexer
+//! follows the line markers the preprocessor leaves, so they refer to the original `.c` or header file.
+
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub file: Rc<str>,
+    pub offset: usize,
+    pub len: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Span {
+    /// The span from the start of `self` to the end of `end`.
+    pub fn to(&self, end: &Span) -> Span {
+        Span { len: (end.offset + end.len).saturating_sub(self.offset), ..self.clone() }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
@@ -0,0 +1,216 @@
+//! # Textual TACKY
+//!
+//! Parses the text that `Display` prints for a `TackyProgram`, so the backend can be driven by `--from-tacky`:
+//! ```text
+//! # Comments run to the end of the line.
+//! function main:
+//!     tmp0 = -5
+//!     tmp1 = ~tmp0
+//!     return tmp1
+//! ```
+//! The syntax is line-based, with one instruction per line:
+//! - `function <name>:` starts the function, which must come before any instruction;
+//! - `<variable> = <operator><operand>` is a unary instruction, with `~` or `-` as its operator;
+//! - `return <operand>` returns an operand;
+//! - An operand is an unsigned 32-bit constant or a variable. Names are made of letters, digits, `_` and `.`, and
+//!   don't start with a digit.
+//!
+//! A variable has to be assigned before it's read, since the backends allocate it where it's first written, and the
+//! function has to end with `return`, since the backends don't add one.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::ast_nodes::*;
+use crate::diagnostics::{Diagnostic, MALFORMED_TACKY, UNDEFINED_TACKY_VARIABLE};
+use crate::span::Span;
+
+#[derive(Debug, PartialEq)]
+enum Word<'a> {
+    Name(&'a str),
+    Integer(&'a str),
+    Punct(char),
+}
+
+/// Parses the TACKY program in `src`, read from `file`.
+pub fn parse_tacky_program(src: &str, file: &str) -> Result<TackyProgram, Vec<Diagnostic>> {
+    let file: Rc<str> = file.into();
+    let mut function: Option<(TackyIdentifier, Vec<TackyInstruction>, Span)> = None;
+    let mut defined = HashSet::new();
+    let mut diagnostics = Vec::new();
+    let mut offset = 0;
+    for (n, line) in src.split_inclusive('\n').enumerate() {
+        let mut words = Words { line: line.trim_end(), column: 0, line_span: Span { file: file.clone(), offset, len: 0, line: n as u32 + 1, column: 1 } };
+        offset += line.len();
+        let result = match function.as_mut() {
+            _ if words.at_end() => continue,
+            None => words.parse_function_header().map(|(name, span)| function = Some((name, Vec::new(), span))),
+            Some(_) if words.peek_is(&Word::Name("function")) => Err(words.error("only one function can be defined")),
+            Some((_, instructions, _)) => words.parse_instruction(&mut defined).map(|instruction| instructions.push(instruction)),
+        };
+        if let Err(diagnostic) = result {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    match function {
+        _ if !diagnostics.is_empty() => Err(diagnostics),
+        None => Err(vec![Diagnostic::error(format!("expected `function <name>:` in `{file}`")).with_code(MALFORMED_TACKY)]),
+        Some((name, instructions, span)) if !matches!(instructions.last(), Some(tacky::Return(..))) => Err(vec![
+            Diagnostic::error(format!("function `{name}` doesn't end with `return`"))
+                .with_code(MALFORMED_TACKY)
+                .with_primary(span, "control reaches the end of this function"),
+        ]),
+        Some((name, instructions, span)) => Ok(tacky::Program(tacky::Function(name, instructions, span))),
+    }
+}
+
+struct Words<'a> {
+    line: &'a str,
+    column: usize,
+    line_span: Span,
+}
+
+impl<'a> Words<'a> {
+    fn skip_space(&mut self) {
+        let rest = &self.line[self.column..];
+        self.column += rest.len() - rest.trim_start().len();
+        if self.line[self.column..].starts_with('#') {
+            self.column = self.line.len();
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_space();
+        self.column == self.line.len()
+    }
+
+    fn span(&self, column: usize, len: usize) -> Span {
+        Span { offset: self.line_span.offset + column, len, column: column as u32 + 1, ..self.line_span.clone() }
+    }
+
+    // The next word and its span, without consuming it.
+    fn peek(&mut self) -> Option<(Word<'a>, Span)> {
+        self.skip_space();
+        let rest = &self.line[self.column..];
+        let first = rest.chars().next()?;
+        let len = match first {
+            '0'..='9' => rest.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(rest.len()),
+            ch if ch.is_ascii_alphabetic() || ch == '_' || ch == '.' => rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '.'))
+                .unwrap_or(rest.len()),
+            ch => ch.len_utf8(),
+        };
+        let word = match first {
+            '0'..='9' => Word::Integer(&rest[..len]),
+            ch if len == ch.len_utf8() && !(ch.is_ascii_alphabetic() || ch == '_' || ch == '.') => Word::Punct(ch),
+            _ => Word::Name(&rest[..len]),
+        };
+        Some((word, self.span(self.column, len)))
+    }
+
+    fn peek_is(&mut self, expected: &Word) -> bool {
+        self.peek().is_some_and(|(word, _)| word == *expected)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(Word<'a>, Span), Diagnostic> {
+        let (word, span) = self.peek().ok_or_else(|| {
+            let end = self.span(self.line.len(), 0);
+            Diagnostic::error(format!("expected {expected}, found end of line"))
+                .with_code(MALFORMED_TACKY)
+                .with_primary(end, format!("expected {expected}"))
+        })?;
+        self.column += span.len;
+        Ok((word, span))
+    }
+
+    fn unexpected(&self, expected: &str, span: Span) -> Diagnostic {
+        let found = &self.line[span.column as usize - 1..][..span.len];
+        Diagnostic::error(format!("expected {expected}, found `{found}`"))
+            .with_code(MALFORMED_TACKY)
+            .with_primary(span, format!("expected {expected}"))
+    }
+
+    fn error(&mut self, message: &str) -> Diagnostic {
+        let span = self.peek().map(|(_, span)| span).unwrap_or(self.line_span.clone());
+        let span = Span { len: self.line.len() - (span.column as usize - 1), ..span };
+        Diagnostic::error(message).with_code(MALFORMED_TACKY).with_primary(span, "")
+    }
+
+    fn expect(&mut self, expected: Word) -> Result<(), Diagnostic> {
+        let description = match expected {
+            Word::Name(name) | Word::Integer(name) => format!("`{name}`"),
+            Word::Punct(ch) => format!("`{ch}`"),
+        };
+        match self.next(&description)? {
+            (word, _) if word == expected => Ok(()),
+            (_, span) => Err(self.unexpected(&description, span)),
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), Diagnostic> {
+        match self.peek() {
+            None => Ok(()),
+            Some((_, span)) => Err(self.unexpected("end of line", span)),
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<(String, Span), Diagnostic> {
+        match self.next("a name")? {
+            (Word::Name(name), span) => Ok((name.into(), span)),
+            (_, span) => Err(self.unexpected("a name", span)),
+        }
+    }
+
+    // The span from `start` to the end of the last word consumed.
+    fn span_from(&self, start: usize) -> Span {
+        self.span(start, self.column - start)
+    }
+
+    fn parse_function_header(&mut self) -> Result<(TackyIdentifier, Span), Diagnostic> {
+        self.skip_space();
+        let start = self.column;
+        self.expect(Word::Name("function"))?;
+        let (name, _) = self.parse_name()?;
+        self.expect(Word::Punct(':'))?;
+        let span = self.span_from(start);
+        self.expect_end()?;
+        Ok((tacky::Identifier(name), span))
+    }
+
+    fn parse_instruction(&mut self, defined: &mut HashSet<String>) -> Result<TackyInstruction, Diagnostic> {
+        self.skip_space();
+        let start = self.column;
+        let (name, _) = self.parse_name()?;
+        let instruction = if name == "return" {
+            tacky::Return(self.parse_operand(defined)?, self.span_from(start))
+        } else {
+            self.expect(Word::Punct('='))?;
+            let operator = match self.next("`~` or `-`")? {
+                (Word::Punct('~'), _) => tacky::Complement,
+                (Word::Punct('-'), _) => tacky::Negate,
+                (_, span) => return Err(self.unexpected("`~` or `-`", span)),
+            };
+            let src = self.parse_operand(defined)?;
+            defined.insert(name.clone());
+            tacky::Unary(operator, src, tacky::Variable(tacky::Identifier(name)), self.span_from(start))
+        };
+        self.expect_end()?;
+        Ok(instruction)
+    }
+
+    fn parse_operand(&mut self, defined: &HashSet<String>) -> Result<TackyOperand, Diagnostic> {
+        match self.next("a constant or a variable")? {
+            (Word::Integer(integer), span) => integer.parse().map(tacky::Constant).map_err(|_| {
+                Diagnostic::error(format!("constant `{integer}` doesn't fit in 32 bits"))
+                    .with_code(MALFORMED_TACKY)
+                    .with_primary(span, "")
+            }),
+            (Word::Name(name), span) if !defined.contains(name) => Err(Diagnostic::error(format!("variable `{name}` is read before it's assigned"))
+                .with_code(UNDEFINED_TACKY_VARIABLE)
+                .with_primary(span, "not assigned yet")),
+            (Word::Name(name), _) => Ok(tacky::Variable(tacky::Identifier(name.into()))),
+            (_, span) => Err(self.unexpected("a constant or a variable", span)),
+        }
+    }
+}
@@ -10,21 +10,21 @@ fn gen_program(c_program: CProgram) -> TackyProgram {
 }
 
 fn gen_function_definition(c_function_definition: CFunctionDefinition) -> TackyFunctionDefinition {
-    let c::Function(c::Identifier(name), c::Return(expression)) = c_function_definition;
+    let c::Function(c::Identifier(name, _), c::Return(expression, return_span), span) = c_function_definition;
     let (mut instructions, operand) = gen_expression(expression);
-    instructions.push(tacky::Return(operand));
-    tacky::Function(tacky::Identifier(name), instructions)
+    instructions.push(tacky::Return(operand, return_span));
+    tacky::Function(tacky::Identifier(name), instructions, span)
 }
 
 fn gen_expression(c_expression: CExpression) -> (Vec<TackyInstruction>, TackyOperand) {
     match c_expression {
-        c::Constant(integer) => {
+        c::Constant(integer, _) => {
             (vec![], tacky::Constant(integer))
         },
-        c::Unary(operator, inner) => {
+        c::Unary(operator, inner, span) => {
             let (mut instructions, src) = gen_expression(*inner);
             let dst = tacky::Variable(tacky::Identifier(format!("tmp{}", instructions.len())));
-            instructions.push(TackyInstruction::Unary(gen_unary_operator(operator), src, dst.clone()));
+            instructions.push(TackyInstruction::Unary(gen_unary_operator(operator), src, dst.clone(), span));
             (instructions, dst)
         },
     }
@@ -0,0 +1,128 @@
+//! # Warnings
+//!
+//! Checks on the C AST that report `Severity::Warning` diagnostics. Each warning has a name for `-W<name>` and
+//! `-Wno-<name>`, and the last of the two on the command line wins.
+//!
+//! The only warning is `-Wconversion`, which like gcc's is in neither `-Wall` nor `-Wextra`: the returned expression
+//! is a constant, so it's folded in `long` and compared to the range of `int`. Warnings about variables, parameters,
+//! comparisons or missing returns are left out, since the grammar only allows `int main(void) { return <expression>; }`
+//! and they could never fire.
+
+use std::collections::HashSet;
+
+use crate::ast_nodes::*;
+use crate::diagnostics::Diagnostic;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Warning {
+    Conversion,
+}
+
+use Warning::*;
+
+impl Warning {
+    const ALL: [Warning; 1] = [Conversion];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Conversion => "conversion",
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Conversion => "W0001",
+        }
+    }
+}
+
+impl TryFrom<&str> for Warning {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL.into_iter().find(|warning| warning.name() == value)
+            .ok_or(format!("Unknown warning `-W{value}`"))
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct WarningOptions {
+    enabled: HashSet<Warning>,
+    werror: bool,
+}
+
+impl WarningOptions {
+    pub fn set(&mut self, warning: Warning, enabled: bool) {
+        if enabled {
+            self.enabled.insert(warning);
+        } else {
+            self.enabled.remove(&warning);
+        }
+    }
+
+    pub fn is_enabled(&self, warning: Warning) -> bool {
+        self.enabled.contains(&warning)
+    }
+
+    pub fn set_werror(&mut self) {
+        self.werror = true;
+    }
+
+    pub fn werror(&self) -> bool {
+        self.werror
+    }
+
+    fn warn(&self, warning: Warning, diagnostic: Diagnostic) -> Diagnostic {
+        diagnostic
+            .with_code(warning.code())
+            .with_note(format!("`-W{}` is enabled on the command line", warning.name()))
+    }
+}
+
+pub fn check_c_program(c_program: &CProgram, options: &WarningOptions) -> Vec<Diagnostic> {
+    let mut warnings = Vec::new();
+    let c::Program(c::Function(_, c::Return(expression, _), _)) = c_program;
+    if options.is_enabled(Conversion) {
+        check_conversion(expression, options, &mut warnings);
+    }
+    warnings
+}
+
+fn check_conversion(expression: &CExpression, options: &WarningOptions, warnings: &mut Vec<Diagnostic>) {
+    let value = fold_constant(expression);
+    let converted = value as i32;
+    if converted as i64 == value {
+        return;
+    }
+    let span = match expression {
+        c::Constant(_, span) | c::Unary(_, _, span) => span.clone(),
+    };
+    let diagnostic = Diagnostic::warning(format!("implicit conversion to `int` changes value from {value} to {converted}"))
+        .with_primary(span, "this constant expression doesn't fit in `int`");
+    warnings.push(options.warn(Conversion, diagnostic));
+}
+
+// Integer constants above `INT_MAX` have type `long`, so the whole expression is evaluated in `long`.
+fn fold_constant(expression: &CExpression) -> i64 {
+    match expression {
+        c::Constant(integer, _) => *integer as i64,
+        c::Unary(c::Negate, inner, _) => fold_constant(inner).wrapping_neg(),
+        c::Unary(c::Complement, inner, _) => !fold_constant(inner),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warning_options() {
+        let mut warning_options = WarningOptions::default();
+        assert!(!warning_options.is_enabled(Conversion));
+        warning_options.set(Warning::try_from("conversion").unwrap(), true);
+        assert!(warning_options.is_enabled(Conversion));
+        warning_options.set(Conversion, false);
+        assert!(!warning_options.is_enabled(Conversion));
+        assert_eq!(Warning::try_from("return-type").unwrap_err(), "Unknown warning `-Wreturn-type`");
+    }
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{check_golden, programs, reference_exit_code, run, scratch_copy, wacc};
+
+const TARGET: &str = "aarch64-linux-gnu";
+
+#[test]
+fn golden_assembly() {
+    let source = scratch_copy("aarch64-golden", Path::new("tests/programs/large_constant.c"));
+    wacc(&["--target", TARGET, "-S"], &source);
+    let actual = fs::read_to_string(source.with_extension("s")).expect("That `wacc -S` should write assembly");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&actual, Path::new("tests/golden/aarch64/large_constant.s"));
+}
+
+// 1100 temporaries of 4 bytes each need a frame beyond the 12-bit immediates of `add`/`sub`.
+#[test]
+fn large_frame() {
+    let scratch = std::env::temp_dir().join(format!("wacc-aarch64-large-frame-{}", std::process::id()));
+    fs::create_dir_all(&scratch).unwrap();
+    let source = scratch.join("large_frame.tacky");
+    let mut tacky = String::from("function main:\n    t0 = -2\n");
+    for i in 1..1100 {
+        tacky.push_str(&format!("    t{i} = ~t{}\n", i - 1));
+    }
+    tacky.push_str("    return t1099\n");
+    fs::write(&source, tacky).unwrap();
+    wacc(&["--from-tacky", "--target", TARGET, "-S"], &source);
+    let assembly = fs::read_to_string(source.with_extension("s")).expect("That `wacc -S` should write assembly");
+    fs::remove_dir_all(&scratch).ok();
+
+    assert!(assembly.contains("\tmovz\tx16, #4400\n\tsub\tsp, sp, x16\n"), "{assembly}");
+    assert!(assembly.contains("\tmovz\tx16, #4400\n\tsub\tx16, x29, x16\n\tstr\tw9, [x16]\n"), "{assembly}");
+    for line in assembly.lines().filter(|line| line.starts_with("\tsub\t") || line.starts_with("\tadd\t")) {
+        if let Some((_, immediate)) = line.rsplit_once('#') {
+            assert!(immediate.parse::<u32>().unwrap() <= 4095, "{line}");
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs the `aarch64-linux-gnu-gcc` cross toolchain and `qemu-aarch64`"]
+fn run_under_qemu() {
+    for program in programs() {
+        let source = scratch_copy("aarch64-qemu", &program);
+        wacc(&["--target", TARGET], &source);
+        let executable = source.with_extension("");
+        let code = run("qemu-aarch64", &["-L".as_ref(), "/usr/aarch64-linux-gnu".as_ref(), executable.as_os_str()]);
+        assert_eq!(code, reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+use common::{programs, reference_exit_code, run, scratch_copy, wacc};
+
+// The symbols and instructions in `.text`, without addresses or bytes: the integrated assembler picks its own
+// encodings, so they can be longer than gas's.
+fn disassemble(object: &Path) -> String {
+    let output = Command::new("objdump").args(["-d", "--no-show-raw-insn"]).arg(object)
+        .output().expect("That objdump should be executed");
+    assert!(output.status.success(), "objdump failed on {}", object.display());
+    String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| match line.split_once(":\t") {
+            Some((_, instruction)) => Some(instruction.split_whitespace().collect::<Vec<_>>().join(" ")),
+            None => line.ends_with(">:").then(|| line.split_once(' ').map_or(line, |(_, label)| label).to_owned()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+#[ignore = "needs binutils' `objdump`"]
+fn same_disassembly_as_gas() {
+    for args in [&[][..], &["-O"], &["--symbol-prefix", "_"]] {
+        for program in programs() {
+            let source = scratch_copy("assembler-disassembly", &program);
+            let object = source.with_extension("o");
+            wacc(&[args, &["-c"]].concat(), &source);
+            let integrated = disassemble(&object);
+            assert!(integrated.ends_with("ret"), "{integrated}");
+            wacc(&[args, &["-c", "-fno-integrated-as"]].concat(), &source);
+            assert_eq!(integrated, disassemble(&object), "wacc {args:?} -c {}", program.display());
+            std::fs::remove_dir_all(source.parent().unwrap()).ok();
+        }
+    }
+}
+
+// Objects from the integrated assembler link with the system linker, both through `wacc` and directly.
+#[test]
+fn links() {
+    for program in programs() {
+        let source = scratch_copy("assembler-links", &program);
+        let expected = reference_exit_code(&source);
+        let executable = source.with_extension("");
+        wacc(&[], &source);
+        assert_eq!(run(executable.to_str().unwrap(), &[] as &[&str]), expected, "{}", program.display());
+
+        wacc(&["-c"], &source);
+        let linked = Command::new("gcc").arg(source.with_extension("o")).arg("-o").arg(&executable)
+            .status().expect("That gcc should be executed");
+        assert!(linked.success());
+        assert_eq!(run(executable.to_str().unwrap(), &[] as &[&str]), expected, "{}", program.display());
+        std::fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
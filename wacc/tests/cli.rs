@@ -0,0 +1,200 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::{Duration, Instant};
+use common::{run, WACC};
+
+const MAIN: &str = "int main(void) { return ~(-3); }\n";
+const HELPER: &str = "\t.globl helper\nhelper:\n\tmovl $7, %eax\n\tret\n";
+
+fn scratch_dir(tag: &str) -> PathBuf {
+    let scratch = std::env::temp_dir().join(format!("wacc-cli-{tag}-{}", std::process::id()));
+    fs::create_dir_all(&scratch).expect("That the scratch directory should be created");
+    scratch
+}
+
+fn wacc_in(scratch: &Path, args: &[&str]) -> Output {
+    Command::new(WACC).args(args).current_dir(scratch).output().expect("That `wacc` should be executed")
+}
+
+fn write(scratch: &Path, name: &str, contents: &str) {
+    fs::write(scratch.join(name), contents).expect("That the input should be written");
+}
+
+#[test]
+fn output_path() {
+    let scratch = scratch_dir("output");
+    write(&scratch, "main.c", MAIN);
+    assert!(wacc_in(&scratch, &["main.c", "-o", "program"]).status.success());
+    assert!(!scratch.join("main").exists());
+    assert!(!scratch.join("main.s").exists());
+    assert_eq!(run(scratch.join("program").to_str().unwrap(), &[] as &[&str]), 2);
+
+    assert!(wacc_in(&scratch, &["-S", "main.c", "-o", "renamed.s"]).status.success());
+    assert!(scratch.join("renamed.s").exists());
+    fs::remove_dir_all(scratch).ok();
+}
+
+#[test]
+fn compile_only() {
+    let scratch = scratch_dir("compile-only");
+    write(&scratch, "main.c", MAIN);
+    assert!(wacc_in(&scratch, &["-c", "main.c"]).status.success());
+    assert!(scratch.join("main.o").exists());
+    assert!(!scratch.join("main.s").exists());
+    assert!(!scratch.join("main").exists());
+
+    assert!(wacc_in(&scratch, &["main.o", "-o", "linked"]).status.success());
+    assert_eq!(run(scratch.join("linked").to_str().unwrap(), &[] as &[&str]), 2);
+    fs::remove_dir_all(scratch).ok();
+}
+
+#[test]
+fn multiple_inputs() {
+    let scratch = scratch_dir("multiple-inputs");
+    write(&scratch, "main.c", MAIN);
+    write(&scratch, "helper.s", HELPER);
+    write(&scratch, "other.s", "\t.globl other\nother:\n\tret\n");
+    assert!(wacc_in(&scratch, &["-c", "other.s"]).status.success());
+
+    assert!(wacc_in(&scratch, &["main.c", "helper.s", "other.o", "-lm", "-L", "."]).status.success());
+    assert_eq!(run(scratch.join("main").to_str().unwrap(), &[] as &[&str]), 2);
+    let symbols = Command::new("nm").arg(scratch.join("main")).output().expect("That `nm` should be executed");
+    let symbols = String::from_utf8_lossy(&symbols.stdout);
+    assert!(symbols.contains(" helper\n") && symbols.contains(" other\n"), "{symbols}");
+    fs::remove_dir_all(scratch).ok();
+}
+
+#[test]
+fn usage_errors() {
+    let scratch = scratch_dir("usage-errors");
+    write(&scratch, "main.c", MAIN);
+    write(&scratch, "other.c", MAIN);
+    let stderr = |args: &[&str]| {
+        let output = wacc_in(&scratch, args);
+        assert!(!output.status.success(), "wacc {args:?} unexpectedly succeeded");
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+    assert!(stderr(&["--frobnicate", "main.c"]).contains("Unrecognized option `--frobnicate`"));
+    assert!(stderr(&["main.txt"]).contains("Unrecognized input file `main.txt`"));
+    assert!(stderr(&["-c", "main.c", "other.c", "-o", "out.o"]).contains("Cannot specify `-o`"));
+    assert!(stderr(&["-o"]).contains("Option `-o` expects a value"));
+    assert!(stderr(&[]).contains("No input files"));
+    fs::remove_dir_all(scratch).ok();
+}
+
+#[test]
+fn help_and_version() {
+    let help = Command::new(WACC).arg("--help").output().expect("That `wacc` should be executed");
+    assert!(help.status.success());
+    assert!(String::from_utf8_lossy(&help.stdout).starts_with("Usage: wacc"));
+
+    let version = Command::new(WACC).arg("--version").output().expect("That `wacc` should be executed");
+    assert!(version.status.success());
+    assert_eq!(String::from_utf8_lossy(&version.stdout), format!("wacc {}\n", env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn quiet_by_default() {
+    let scratch = scratch_dir("quiet");
+    write(&scratch, "main.c", MAIN);
+    let output = wacc_in(&scratch, &["main.c"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = wacc_in(&scratch, &["-v", "-fuse-ld=gcc", "main.c"]);
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("gcc ") && stderr.ends_with("-main.o -o main\n"), "{stderr}");
+    fs::remove_dir_all(scratch).ok();
+}
+
+#[test]
+fn dumps() {
+    let scratch = scratch_dir("dumps");
+    write(&scratch, "main.c", MAIN);
+    let output = wacc_in(&scratch, &["--tacky", "--dump-tokens", "--dump-tacky=main.tacky", "--dump-asm", "main.c"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("main.c:1:1: int\nmain.c:1:5: main\n"), "{stdout}");
+    assert!(stdout.ends_with("main.c:1:32: }\n"), "{stdout}");
+    let tacky = fs::read_to_string(scratch.join("main.tacky")).expect("That the TACKY dump should be written");
+    assert!(tacky.contains("tmp1 = ~tmp0"), "{tacky}");
+
+    let output = wacc_in(&scratch, &["--dump-ast", "--dump-asm=main.asm", "main.c"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("int main(void) {"));
+    let asm = fs::read_to_string(scratch.join("main.asm")).expect("That the assembly dump should be written");
+    assert!(asm.contains("Frame layout of `main`"), "{asm}");
+
+    let output = wacc_in(&scratch, &["--dump-everything", "main.c"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown dump `--dump-everything`"));
+    fs::remove_dir_all(scratch).ok();
+}
+
+// Runs `wacc` with its temporary directory inside `scratch`.
+fn wacc_with_tmpdir(scratch: &Path, args: &[&str]) -> Output {
+    Command::new(WACC).args(args).current_dir(scratch).env("TMPDIR", scratch.join("tmp"))
+        .output().expect("That `wacc` should be executed")
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).expect("That the directory should exist").next().is_none()
+}
+
+#[test]
+fn intermediates_stay_private() {
+    let scratch = scratch_dir("intermediates");
+    fs::create_dir_all(scratch.join("tmp")).unwrap();
+    write(&scratch, "main.c", MAIN);
+    write(&scratch, "main.s", "user file\n");
+
+    assert!(wacc_with_tmpdir(&scratch, &["main.c"]).status.success());
+    assert!(wacc_with_tmpdir(&scratch, &["-c", "main.c"]).status.success());
+    assert!(scratch.join("main").exists() && scratch.join("main.o").exists());
+    assert!(!wacc_with_tmpdir(&scratch, &["main.c", "-lwacc_missing_library"]).status.success());
+    assert!(!wacc_with_tmpdir(&scratch, &["main.c", "missing.c"]).status.success());
+
+    assert_eq!(fs::read_to_string(scratch.join("main.s")).unwrap(), "user file\n");
+    assert!(is_empty_dir(&scratch.join("tmp")));
+    fs::remove_dir_all(scratch).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_cleans_up() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::process::CommandExt;
+
+    let scratch = scratch_dir("interrupt");
+    fs::create_dir_all(scratch.join("tmp")).unwrap();
+    fs::create_dir_all(scratch.join("bin")).unwrap();
+    write(&scratch, "main.c", MAIN);
+    // A gcc that hangs until Ctrl-C, which reaches it through the process group like it would from a terminal.
+    write(&scratch, "bin/gcc", "#!/bin/sh\nexec sleep 30\n");
+    fs::set_permissions(scratch.join("bin/gcc"), fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", scratch.join("bin").display(), std::env::var("PATH").unwrap_or_default());
+    let start = Instant::now();
+    let mut child = Command::new(WACC).args(["-fuse-ld=gcc", "main.c"]).current_dir(&scratch).env("PATH", path).env("TMPDIR", scratch.join("tmp"))
+        .process_group(0).spawn().expect("That `wacc` should be executed");
+    while !has_intermediate(&scratch.join("tmp")) {
+        assert!(start.elapsed() < Duration::from_secs(10), "`wacc` never wrote its assembly");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    let status = Command::new("kill").args(["-INT", "--", &format!("-{}", child.id())]).status().unwrap();
+    assert!(status.success());
+
+    assert_eq!(child.wait().unwrap().code(), Some(130));
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert!(is_empty_dir(&scratch.join("tmp")));
+    fs::remove_dir_all(scratch).ok();
+}
+
+fn has_intermediate(tmp: &Path) -> bool {
+    fs::read_dir(tmp).unwrap().flatten().any(|dir| fs::read_dir(dir.path()).is_ok_and(|mut files| files.next().is_some()))
+}
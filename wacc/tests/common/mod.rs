@@ -0,0 +1,68 @@
+#![allow(dead_code)]
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const WACC: &str = env!("CARGO_BIN_EXE_wacc");
+pub const PROGRAMS_DIR: &str = "tests/programs";
+
+// `wacc` writes its outputs next to the source, so every test compiles a copy inside its own scratch directory.
+pub fn scratch_copy(tag: &str, source: &Path) -> PathBuf {
+    static NEXT_SCRATCH: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT_SCRATCH.fetch_add(1, Ordering::Relaxed);
+    let scratch = std::env::temp_dir().join(format!("wacc-{tag}-{}-{n}", std::process::id()));
+    fs::create_dir_all(&scratch).expect("That the scratch directory should be created");
+    let copy = scratch.join(source.file_name().expect("That the source should be a file"));
+    fs::copy(source, &copy).expect("That the source should be copied");
+    copy
+}
+
+pub fn wacc(args: &[&str], source: &Path) -> Output {
+    let output = Command::new(WACC).args(args).arg(source).output().expect("That `wacc` should be executed");
+    assert!(output.status.success(), "wacc {args:?} {} failed:\n{}", source.display(), String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+// Runs `wacc` expecting it to fail and returns what it reported on stderr.
+pub fn wacc_error(args: &[&str], source: &Path) -> String {
+    let output = Command::new(WACC).args(args).arg(source).output().expect("That `wacc` should be executed");
+    assert!(!output.status.success(), "wacc {args:?} {} unexpectedly succeeded", source.display());
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+pub fn run<S: AsRef<OsStr>>(program: &str, args: &[S]) -> i32 {
+    let status = Command::new(program).args(args).status().expect("That the program should be executed");
+    status.code().expect("That the program should exit normally")
+}
+
+// The native gcc build of a test program is the oracle for every other way of running it.
+pub fn reference_exit_code(source: &Path) -> i32 {
+    let executable = source.with_extension("ref");
+    let status = Command::new("gcc").arg(source).arg("-o").arg(&executable).status().expect("That gcc should be executed");
+    assert!(status.success());
+    let code = Command::new(&executable).status().expect("That the reference executable should run").code().unwrap();
+    fs::remove_file(executable).ok();
+    code
+}
+
+pub fn programs() -> Vec<PathBuf> {
+    let mut programs: Vec<PathBuf> = fs::read_dir(PROGRAMS_DIR).expect("That the test programs should exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "c"))
+        .collect();
+    programs.sort();
+    programs
+}
+
+// Compares `actual` with the golden file at `expected_path`; set `WACC_BLESS=1` to overwrite it instead.
+pub fn check_golden(actual: &str, expected_path: &Path) {
+    if std::env::var_os("WACC_BLESS").is_some() {
+        fs::write(expected_path, actual).expect("That the golden file should be written");
+        return;
+    }
+    let expected = fs::read_to_string(expected_path).expect("That the golden file should exist");
+    assert_eq!(actual, expected, "output differs from `{}`", expected_path.display());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{check_golden, programs, reference_exit_code, run, scratch_copy, wacc, wacc_error};
+
+const GOLDEN_DIR: &str = "tests/golden/debug_info";
+
+fn readelf(args: &[&str], path: &Path) -> String {
+    let output = Command::new("readelf").args(args).arg(path).output().expect("That readelf should be executed");
+    assert!(output.status.success(), "readelf {args:?} failed on {}", path.display());
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+// The compilation directory and the version in the producer change from run to run and release to release.
+#[test]
+fn directives() {
+    let source = scratch_copy("debug-info-directives", &Path::new(common::PROGRAMS_DIR).join("nested_unary.c"));
+    let scratch = source.parent().unwrap();
+    let output = Command::new(common::WACC).args(["-S", "-g", "nested_unary.c"]).current_dir(scratch)
+        .status().expect("That `wacc` should be executed");
+    assert!(output.success());
+    let actual = fs::read_to_string(source.with_extension("s")).unwrap()
+        .replace(&format!("\"{}\"", scratch.display()), "\"<scratch>\"")
+        .replace(concat!("\"wacc ", env!("CARGO_PKG_VERSION"), "\""), "\"wacc <version>\"");
+    fs::remove_dir_all(scratch).ok();
+    check_golden(&actual, &Path::new(GOLDEN_DIR).join("nested_unary.s"));
+}
+
+#[test]
+fn executables() {
+    for args in [&[][..], &["-O"], &["--asm-syntax", "intel"]] {
+        for program in programs() {
+            let source = scratch_copy("debug-info-executables", &program);
+            wacc(&[args, &["-g"]].concat(), &source);
+            let executable = source.with_extension("");
+            assert_eq!(run(executable.to_str().unwrap(), &[] as &[&str]), reference_exit_code(&source), "{}", program.display());
+
+            let info = readelf(&["--debug-dump=info"], &executable);
+            assert!(info.contains("DW_TAG_subprogram") && info.contains("DW_AT_name        : main"), "{info}");
+            assert!(info.contains("DW_AT_frame_base  : 1 byte block: 56 \t(DW_OP_reg6 (rbp))"), "{info}");
+            let lines = readelf(&["--debug-dump=decodedline"], &executable);
+            let file_name = program.file_name().unwrap().to_str().unwrap();
+            assert!(lines.lines().filter(|line| line.starts_with(file_name)).count() >= 2, "{lines}");
+            let frames = readelf(&["--debug-dump=frames"], &executable);
+            assert!(frames.contains("DW_CFA_def_cfa_register: r6 (rbp)"), "{frames}");
+            fs::remove_dir_all(source.parent().unwrap()).ok();
+        }
+    }
+}
+
+// TACKY carries the spans of its source, whichever it is.
+#[test]
+fn tacky_lines() {
+    let source = scratch_copy("debug-info-tacky", &Path::new("tests/golden/tacky").join("reuse.tacky"));
+    wacc(&["--from-tacky", "-S", "-g"], &source);
+    let assembly = fs::read_to_string(source.with_extension("s")).unwrap();
+    assert!(assembly.contains(&format!(".file 1 \"{}\"", source.display())), "{assembly}");
+    for loc in [".loc 1 2 1", ".loc 1 3 5", ".loc 1 7 5"] {
+        assert!(assembly.contains(loc), "{loc} missing in:\n{assembly}");
+    }
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
+
+#[test]
+fn unsupported_targets() {
+    let source = scratch_copy("debug-info-targets", &Path::new(common::PROGRAMS_DIR).join("return_constant.c"));
+    for args in [&["--target", "aarch64-linux-gnu"][..], &["--object-format", "mach-o"]] {
+        let stderr = wacc_error(&[args, &["-S", "-g"]].concat(), &source);
+        assert!(stderr.contains("`-g` is only supported for x86-64 ELF"), "{stderr}");
+    }
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{check_golden, scratch_copy, wacc, wacc_error};
+
+const GOLDEN_DIR: &str = "tests/golden/diagnostics";
+
+fn check_diagnostics_golden(name: &str) {
+    check_formatted_golden(name, &[], "stderr");
+}
+
+fn check_formatted_golden(name: &str, args: &[&str], extension: &str) {
+    let source = scratch_copy(&format!("diagnostics-{name}"), &Path::new(GOLDEN_DIR).join(format!("{name}.c")));
+    let stderr = wacc_error(args, &source);
+    let stderr = stderr.replace(source.to_str().unwrap(), &format!("{name}.c"));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&stderr, &Path::new(GOLDEN_DIR).join(format!("{name}.{extension}")));
+}
+
+#[test]
+fn missing_semicolon() {
+    check_diagnostics_golden("missing_semicolon");
+}
+
+#[test]
+fn multiple_errors() {
+    check_diagnostics_golden("multiple_errors");
+}
+
+#[test]
+fn decrement() {
+    check_diagnostics_golden("decrement");
+}
+
+#[test]
+fn invalid_tokens() {
+    check_diagnostics_golden("invalid_tokens");
+}
+
+#[test]
+fn bad_header() {
+    check_diagnostics_golden("bad_header");
+}
+
+#[test]
+fn end_of_input() {
+    check_diagnostics_golden("end_of_input");
+}
+
+#[test]
+fn json() {
+    check_formatted_golden("multiple_errors", &["--error-format=json"], "json");
+}
+
+#[test]
+fn sarif() {
+    check_formatted_golden("multiple_errors", &["--error-format", "sarif"], "sarif");
+}
+
+fn compile_with_warnings(name: &str, args: &[&str]) -> String {
+    let source = scratch_copy(&format!("diagnostics-{name}"), &Path::new(GOLDEN_DIR).join(format!("{name}.c")));
+    let output = wacc(args, &source);
+    let stderr = String::from_utf8_lossy(&output.stderr).replace(source.to_str().unwrap(), &format!("{name}.c"));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    stderr
+}
+
+#[test]
+fn lossy_conversion() {
+    let stderr = compile_with_warnings("lossy_conversion", &["-Wconversion"]);
+    check_golden(&stderr, &Path::new(GOLDEN_DIR).join("lossy_conversion.stderr"));
+}
+
+// Like gcc, `-Wconversion` is in neither `-Wall` nor `-Wextra`.
+#[test]
+fn lossy_conversion_disabled() {
+    assert_eq!(compile_with_warnings("lossy_conversion", &[]), "");
+    assert_eq!(compile_with_warnings("lossy_conversion", &["-Wall", "-Wextra"]), "");
+    assert_eq!(compile_with_warnings("lossy_conversion", &["-Wconversion", "-Wno-conversion"]), "");
+}
+
+#[test]
+fn werror() {
+    check_formatted_golden("lossy_conversion", &["-Wconversion", "-Werror"], "werror.stderr");
+}
+
+// The warnings of every input are reported, whichever comes last.
+#[test]
+fn warnings_of_every_input() {
+    let source = scratch_copy("diagnostics-inputs", &Path::new(GOLDEN_DIR).join("lossy_conversion.c"));
+    let scratch = source.parent().unwrap();
+    fs::copy(Path::new(common::PROGRAMS_DIR).join("return_constant.c"), scratch.join("return_constant.c")).unwrap();
+    for inputs in [["lossy_conversion.c", "return_constant.c"], ["return_constant.c", "lossy_conversion.c"]] {
+        let output = Command::new(common::WACC).args(["-S", "-Wconversion"]).args(inputs).current_dir(scratch)
+            .output().expect("That `wacc` should be executed");
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.starts_with("warning[W0001]") && stderr.contains("--> lossy_conversion.c:2:12"), "{inputs:?}:\n{stderr}");
+    }
+    fs::remove_dir_all(scratch).ok();
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{check_golden, programs, reference_exit_code, run, scratch_copy, wacc};
+
+const GOLDEN_DIR: &str = "tests/golden/emit";
+
+fn check_emit_golden(name: &str, args: &[&str], expected: &str) {
+    let source = scratch_copy(&format!("emit-{expected}"), &Path::new("tests/programs").join(format!("{name}.c")));
+    wacc(&[&["-S"], args].concat(), &source);
+    let actual = fs::read_to_string(source.with_extension("s")).expect("That `wacc -S` should write assembly");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&actual, &Path::new(GOLDEN_DIR).join(expected));
+}
+
+#[test]
+fn att_syntax() {
+    check_emit_golden("nested_unary", &["--asm-syntax", "att"], "nested_unary.att.s");
+}
+
+#[test]
+fn intel_syntax() {
+    check_emit_golden("nested_unary", &["--asm-syntax", "intel"], "nested_unary.intel.s");
+}
+
+#[test]
+fn mach_o_symbols_and_sections() {
+    check_emit_golden("nested_unary", &["--object-format", "mach-o"], "nested_unary.mach-o.s");
+}
+
+#[test]
+fn custom_symbol_prefix() {
+    check_emit_golden("return_constant", &["--symbol-prefix", "__wacc_"], "return_constant.prefixed.s");
+}
+
+#[test]
+fn intel_syntax_runs() {
+    for program in programs() {
+        let source = scratch_copy("emit-intel-run", &program);
+        wacc(&["--asm-syntax", "intel"], &source);
+        let code = run(source.with_extension("").to_str().unwrap(), &[] as &[&str]);
+        assert_eq!(code, reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
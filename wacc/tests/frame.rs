@@ -0,0 +1,29 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run, scratch_copy, wacc};
+
+// The `.s` file lists the slots ahead of the prologue, in either syntax.
+#[test]
+fn slot_comments() {
+    // Under register pressure, three temporaries are spilled and five callee-saved registers are pushed.
+    let source = scratch_copy("frame-comments", &Path::new("tests/golden/regalloc").join("pressure.tacky"));
+    let assembly = source.with_extension("s");
+    let expected = [
+        ("\t# -4(%rbp): t0, 4 bytes\n\t# -8(%rbp): t1, 4 bytes\n\t# -12(%rbp): t10, 4 bytes\n", "\tsubq\t$24, %rsp\n"),
+        ("\t# DWORD PTR [rbp-4]: t0, 4 bytes\n\t# DWORD PTR [rbp-8]: t1, 4 bytes\n\t# DWORD PTR [rbp-12]: t10, 4 bytes\n",
+            "\tsub\trsp, 24\n"),
+    ];
+    for (syntax, (comments, allocation)) in ["att", "intel"].into_iter().zip(expected) {
+        wacc(&["--from-tacky", "-S", "--asm-syntax", syntax], &source);
+        let actual = fs::read_to_string(&assembly).unwrap();
+        assert!(actual.contains(&format!("main:\n{comments}")), "{actual}");
+        assert!(actual.contains(allocation), "{actual}");
+    }
+
+    // The pushes leave the stack aligned, which the program relies on when it runs.
+    wacc(&["--from-tacky"], &source);
+    assert_eq!(run(source.with_extension("").to_str().unwrap(), &[] as &[&str]), 255);
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
@@ -0,0 +1,19 @@
+	.globl main
+	.type main, %function
+main:
+	stp	x29, x30, [sp, #-16]!
+	mov	x29, sp
+	sub	sp, sp, #16
+	movz	w9, #34464
+	movk	w9, #1, lsl #16
+	neg	w9, w9
+	stur	w9, [x29, #-4]
+	ldur	w9, [x29, #-4]
+	mvn	w9, w9
+	stur	w9, [x29, #-8]
+	ldur	w0, [x29, #-8]
+	mov	sp, x29
+	ldp	x29, x30, [sp], #16
+	ret
+
+	.section .note.GNU-stack,"",%progbits
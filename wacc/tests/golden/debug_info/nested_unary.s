@@ -0,0 +1,137 @@
+	.file 1 "nested_unary.c"
+	.type main, @function
+	.globl main
+main:
+	.loc 1 1 1
+	.cfi_startproc
+	pushq	%rbp
+	.cfi_def_cfa_offset 16
+	.cfi_offset 6, -16
+	movq	%rsp, %rbp
+	.cfi_def_cfa_register 6
+	subq	$0, %rsp
+	.loc 1 2 16
+	movl	$5, %eax
+	negl	%eax
+	.loc 1 2 14
+	notl	%eax
+	.loc 1 2 12
+	negl	%eax
+	.loc 1 2 5
+	.cfi_remember_state
+	movq	%rbp, %rsp
+	popq	%rbp
+	.cfi_def_cfa 7, 8
+	ret
+	.cfi_restore_state
+	.cfi_endproc
+.Lfunc_end0:
+	.size main, .Lfunc_end0-main
+	.section .debug_abbrev,"",@progbits
+.Ldebug_abbrev0:
+	.uleb128 1
+	.uleb128 0x11
+	.byte 1
+	.uleb128 0x25
+	.uleb128 0x8
+	.uleb128 0x13
+	.uleb128 0xb
+	.uleb128 0x3
+	.uleb128 0x8
+	.uleb128 0x1b
+	.uleb128 0x8
+	.uleb128 0x11
+	.uleb128 0x1
+	.uleb128 0x12
+	.uleb128 0x7
+	.uleb128 0x10
+	.uleb128 0x17
+	.byte 0
+	.byte 0
+	.uleb128 2
+	.uleb128 0x2e
+	.byte 1
+	.uleb128 0x3f
+	.uleb128 0x19
+	.uleb128 0x3
+	.uleb128 0x8
+	.uleb128 0x3a
+	.uleb128 0xf
+	.uleb128 0x3b
+	.uleb128 0xf
+	.uleb128 0x39
+	.uleb128 0xf
+	.uleb128 0x27
+	.uleb128 0x19
+	.uleb128 0x49
+	.uleb128 0x13
+	.uleb128 0x11
+	.uleb128 0x1
+	.uleb128 0x12
+	.uleb128 0x7
+	.uleb128 0x40
+	.uleb128 0x18
+	.byte 0
+	.byte 0
+	.uleb128 3
+	.uleb128 0x34
+	.byte 0
+	.uleb128 0x3
+	.uleb128 0x8
+	.uleb128 0x34
+	.uleb128 0x19
+	.uleb128 0x49
+	.uleb128 0x13
+	.uleb128 0x2
+	.uleb128 0x18
+	.byte 0
+	.byte 0
+	.uleb128 4
+	.uleb128 0x24
+	.byte 0
+	.uleb128 0xb
+	.uleb128 0xb
+	.uleb128 0x3e
+	.uleb128 0xb
+	.uleb128 0x3
+	.uleb128 0x8
+	.byte 0
+	.byte 0
+	.byte 0
+	.section .debug_info,"",@progbits
+.Ldebug_info0:
+	.long .Ldebug_info_end0-.Ldebug_info_start0
+.Ldebug_info_start0:
+	.value 4
+	.long .Ldebug_abbrev0
+	.byte 8
+	.uleb128 1
+	.string "wacc <version>"
+	.byte 0xc
+	.string "nested_unary.c"
+	.string "<scratch>"
+	.quad main
+	.quad .Lfunc_end0-main
+	.long .Ldebug_line0
+.Ldebug_type_int:
+	.uleb128 4
+	.byte 4
+	.byte 0x5
+	.string "int"
+	.uleb128 2
+	.string "main"
+	.uleb128 1
+	.uleb128 1
+	.uleb128 1
+	.long .Ldebug_type_int-.Ldebug_info0
+	.quad main
+	.quad .Lfunc_end0-main
+	.uleb128 1
+	.byte 0x56
+	.byte 0
+	.byte 0
+.Ldebug_info_end0:
+	.section .debug_line,"",@progbits
+.Ldebug_line0:
+
+	.section .note.GNU-stack,"",@progbits
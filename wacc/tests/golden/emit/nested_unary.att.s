@@ -0,0 +1,14 @@
+	.globl main
+main:
+	pushq	%rbp
+	movq	%rsp, %rbp
+	subq	$0, %rsp
+	movl	$5, %eax
+	negl	%eax
+	notl	%eax
+	negl	%eax
+	movq	%rbp, %rsp
+	popq	%rbp
+	ret
+
+	.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,15 @@
+	.intel_syntax noprefix
+	.globl main
+main:
+	push	rbp
+	mov	rbp, rsp
+	sub	rsp, 0
+	mov	eax, 5
+	neg	eax
+	not	eax
+	neg	eax
+	mov	rsp, rbp
+	pop	rbp
+	ret
+
+	.section .note.GNU-stack,"",@progbits
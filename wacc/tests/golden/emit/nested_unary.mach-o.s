@@ -0,0 +1,14 @@
+	.section __TEXT,__text,regular,pure_instructions
+	.globl _main
+_main:
+	pushq	%rbp
+	movq	%rsp, %rbp
+	subq	$0, %rsp
+	movl	$5, %eax
+	negl	%eax
+	notl	%eax
+	negl	%eax
+	movq	%rbp, %rsp
+	popq	%rbp
+	ret
+
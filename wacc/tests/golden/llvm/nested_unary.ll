@@ -0,0 +1,19 @@
+; ModuleID = "nested_unary.c"
+source_filename = "nested_unary.c"
+
+define i32 @main() {
+entry:
+  %var.tmp0 = alloca i32, align 4
+  %var.tmp1 = alloca i32, align 4
+  %var.tmp2 = alloca i32, align 4
+  %v0 = sub i32 0, 5
+  store i32 %v0, ptr %var.tmp0, align 4
+  %v1 = load i32, ptr %var.tmp0, align 4
+  %v2 = xor i32 %v1, -1
+  store i32 %v2, ptr %var.tmp1, align 4
+  %v3 = load i32, ptr %var.tmp1, align 4
+  %v4 = sub i32 0, %v3
+  store i32 %v4, ptr %var.tmp2, align 4
+  %v5 = load i32, ptr %var.tmp2, align 4
+  ret i32 %v5
+}
@@ -0,0 +1,13 @@
+	.globl main
+main:
+	pushq	%rbp
+	movq	%rsp, %rbp
+	movl	$5, %eax
+	negl	%eax
+	notl	%eax
+	negl	%eax
+	movq	%rbp, %rsp
+	popq	%rbp
+	ret
+
+	.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,10 @@
+	.globl main
+main:
+	pushq	%rbp
+	movq	%rsp, %rbp
+	xorl	%eax, %eax
+	movq	%rbp, %rsp
+	popq	%rbp
+	ret
+
+	.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,6 @@
+#define DIVISOR 0
+#if 1 / DIVISOR
+#endif
+int main(void) {
+    return 0;
+}
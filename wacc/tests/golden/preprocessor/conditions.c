@@ -0,0 +1,22 @@
+// Each `#error` is reached only if the condition above it is evaluated differently from gcc.
+#if 0 && 1 / 0
+#error `&&` evaluated its right operand
+#endif
+#if !(1 || 1 % 0)
+#error `||` evaluated its right operand
+#endif
+#if !(0 ? 1 / 0 : 1) || (1 ? 0 : 1 % 0)
+#error `?:` evaluated the branch not taken
+#endif
+#if !(-1 > 0u) || !(0xffffffffffffffff > 0) || -1 >= 0
+#error unsigned operands compare as `uintmax_t`
+#endif
+#if (0u - 1) >> 63 != 1 || -1 >> 63 != -1
+#error `>>` shifts in sign bits of signed operands only
+#endif
+#if (1 ? -1 : 0u) < 0
+#error `?:` gives an unsigned result if either branch is unsigned
+#endif
+int main(void) {
+    return -(~7);
+}
@@ -0,0 +1,6 @@
+#ifndef UNDEFINED
+#error UNDEFINED is not defined
+#endif
+int main(void) {
+    return 0;
+}
@@ -0,0 +1,3 @@
+#line 10 "tab\there \303\274 \"q\".c"
+__FILE__
+int main(void) { return; }
@@ -0,0 +1,10 @@
+#pragma once
+
+#define NEGATE(x) (-(x))
+#define PASTE(a, b) a ## b
+#define STRINGIFY(x) #x
+#define EXPAND_STRINGIFY(x) STRINGIFY(x)
+#define FIRST(first, ...) first
+#define REST(first, ...) __VA_ARGS__
+
+#define VALUE 40
@@ -0,0 +1,28 @@
+#include "macros.h"
+#include <macros.h> // skipped by `#pragma once`
+
+/* A block comment
+   spanning two lines */
+#if defined(VALUE) && VALUE * 2 > 0x4f || 0
+#define RESULT PASTE(VAL, UE)
+#elif 1
+#error `#elif` after a taken branch
+#else
+#error `#else` after a taken branch
+#endif
+
+#ifndef RESULT
+#error RESULT should be defined
+#endif
+
+#undef VALUE
+#define VALUE 2
+#define f(a) a*g
+#define g(a) f(a)
+
+int main(void) {
+    f(2)(9);
+    EXPAND_STRINGIFY(__LINE__) STRINGIFY("a\n" 'b') __FILE__;
+    FIRST(return, ignored) ~REST(ignored, NEGATE( \
+        RESULT));
+}
@@ -0,0 +1,40 @@
+# 1 "macros.c"
+# 1 "include/macros.h"
+
+
+
+
+
+
+
+
+
+
+# 2 "macros.c"
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+int main(void) {
+2*9*g      ;
+"25" "\"a\\n\" 'b'"                                 "macros.c";
+return                     ~(-(2))                                ;
+
+}
@@ -0,0 +1,11 @@
+#include "macros.h"
+#define APPLY(f, x) f(x)
+#define COMPLEMENT(x) ~x
+
+int main(void) {
+#if __STDC_VERSION__ >= 201112L && '\n' == 10
+    return APPLY(COMPLEMENT, APPLY(NEGATE, PASTE(VAL, UE)));
+#else
+    return 0;
+#endif
+}
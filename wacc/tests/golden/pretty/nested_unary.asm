@@ -0,0 +1,10 @@
+main:
+    allocate_stack 0
+    mov $5, %ax
+    neg %ax
+    not %ax
+    neg %ax
+    ret
+
+Frame layout of `main`:
+frame size: 0 bytes
@@ -0,0 +1,39 @@
+# Calls `wacc_main` with known values in the callee-saved registers, and exits with 200 if they aren't restored.
+    .globl main
+main:
+    push %rbx
+    push %r12
+    push %r13
+    push %r14
+    push %r15
+    movabs $0x1111111111111111, %rbx
+    movabs $0x1212121212121212, %r12
+    movabs $0x1313131313131313, %r13
+    movabs $0x1414141414141414, %r14
+    movabs $0x1515151515151515, %r15
+    call wacc_main
+    movabs $0x1111111111111111, %rcx
+    cmp %rcx, %rbx
+    jne .Lclobbered
+    movabs $0x1212121212121212, %rcx
+    cmp %rcx, %r12
+    jne .Lclobbered
+    movabs $0x1313131313131313, %rcx
+    cmp %rcx, %r13
+    jne .Lclobbered
+    movabs $0x1414141414141414, %rcx
+    cmp %rcx, %r14
+    jne .Lclobbered
+    movabs $0x1515151515151515, %rcx
+    cmp %rcx, %r15
+    je .Lrestored
+.Lclobbered:
+    movl $200, %eax
+.Lrestored:
+    pop %r15
+    pop %r14
+    pop %r13
+    pop %r12
+    pop %rbx
+    ret
+    .section .note.GNU-stack,"",@progbits
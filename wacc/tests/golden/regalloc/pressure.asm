@@ -0,0 +1,80 @@
+main:
+    allocate_stack 24
+    push %bx
+    push %r12
+    push %r13
+    push %r14
+    push %r15
+    mov $1, -4(%rbp)
+    neg -4(%rbp)
+    mov $2, -8(%rbp)
+    neg -8(%rbp)
+    mov $3, %r11
+    neg %r11
+    mov $4, %r9
+    neg %r9
+    mov $5, %r8
+    neg %r8
+    mov $6, %di
+    neg %di
+    mov $7, %si
+    neg %si
+    mov $8, %dx
+    neg %dx
+    mov $9, %cx
+    neg %cx
+    mov $10, %ax
+    neg %ax
+    mov $11, -12(%rbp)
+    neg -12(%rbp)
+    mov $12, %r15
+    neg %r15
+    mov $13, %r14
+    neg %r14
+    mov $14, %r13
+    neg %r13
+    mov $15, %r12
+    neg %r12
+    mov $16, %bx
+    neg %bx
+    not %bx
+    mov %r12, %bx
+    not %bx
+    mov %r13, %bx
+    not %bx
+    mov %r14, %bx
+    not %bx
+    mov %r15, %bx
+    not %bx
+    mov -12(%rbp), %bx
+    not %bx
+    not %ax
+    mov %cx, %ax
+    not %ax
+    mov %dx, %ax
+    not %ax
+    mov %si, %ax
+    not %ax
+    mov %di, %ax
+    not %ax
+    mov %r8, %ax
+    not %ax
+    mov %r9, %ax
+    not %ax
+    mov %r11, %ax
+    not %ax
+    mov -8(%rbp), %ax
+    not %ax
+    mov -4(%rbp), %ax
+    pop %r15
+    pop %r14
+    pop %r13
+    pop %r12
+    pop %bx
+    ret
+
+Frame layout of `main`:
+frame size: 24 bytes
+     -4: t0 (4 bytes, align 4)
+     -8: t1 (4 bytes, align 4)
+    -12: t10 (4 bytes, align 4)
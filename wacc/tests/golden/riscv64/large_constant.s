@@ -0,0 +1,23 @@
+	.globl main
+	.type main, @function
+main:
+	addi	sp, sp, -16
+	sd	ra, 8(sp)
+	sd	s0, 0(sp)
+	addi	s0, sp, 16
+	addi	sp, sp, -16
+	lui	t0, 24
+	addiw	t0, t0, 1696
+	negw	t0, t0
+	sw	t0, -20(s0)
+	lw	t0, -20(s0)
+	not	t0, t0
+	sw	t0, -24(s0)
+	lw	a0, -24(s0)
+	addi	sp, s0, -16
+	ld	ra, 8(sp)
+	ld	s0, 0(sp)
+	addi	sp, sp, 16
+	ret
+
+	.section .note.GNU-stack,"",@progbits
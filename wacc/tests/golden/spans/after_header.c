@@ -0,0 +1,4 @@
+int main(void) {
+#include "comment.h"
+    return 0
+}
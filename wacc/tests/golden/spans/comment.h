@@ -0,0 +1 @@
+// Only a comment
@@ -0,0 +1,17 @@
+# 0 "gcc_line_marker.c"
+# 0 "<built-in>"
+# 0 "<command-line>"
+# 1 "/usr/include/stdc-predef.h" 1 3 4
+# 0 "<command-line>" 2
+# 1 "gcc_line_marker.c"
+# 1 "sys/answer.h" 1 3 4
+
+
+# 2 "sys/answer.h" 3 4
+int answer(void);
+# 2 "gcc_line_marker.c" 2
+
+# 2 "gcc_line_marker.c"
+int main(void) {
+    return $;
+}
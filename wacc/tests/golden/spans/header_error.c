@@ -0,0 +1,5 @@
+/* The body comes
+   from a header. */
+int main(void) {
+#include "body.h"
+}
@@ -0,0 +1,4 @@
+int main(void) {
+#line 100 "generated.c"
+    return $;
+}
@@ -0,0 +1,12 @@
+main:
+    allocate_stack 0
+    mov $5, %ax
+    neg %ax
+    mov %ax, %cx
+    not %cx
+    neg %ax
+    not %ax
+    ret
+
+Frame layout of `main`:
+frame size: 0 bytes
@@ -0,0 +1,39 @@
+(module
+  (memory (export "memory") 1)
+  (global $__stack_pointer (mut i32) (i32.const 65536))
+  (func $main (export "main") (result i32)
+    (local $fp i32)
+    ;; 12($fp): tmp0, 4 bytes
+    ;; 8($fp): tmp1, 4 bytes
+    ;; 4($fp): tmp2, 4 bytes
+    global.get $__stack_pointer
+    i32.const 16
+    i32.sub
+    local.tee $fp
+    global.set $__stack_pointer
+    local.get $fp
+    i32.const 0
+    i32.const 5
+    i32.sub
+    i32.store offset=12
+    local.get $fp
+    local.get $fp
+    i32.load offset=12
+    i32.const -1
+    i32.xor
+    i32.store offset=8
+    local.get $fp
+    i32.const 0
+    local.get $fp
+    i32.load offset=8
+    i32.sub
+    i32.store offset=4
+    local.get $fp
+    i32.load offset=4
+    local.get $fp
+    i32.const 16
+    i32.add
+    global.set $__stack_pointer
+    return
+  )
+)
@@ -0,0 +1,16 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+use common::{programs, reference_exit_code, scratch_copy, WACC};
+
+// The native gcc build is the oracle: interpreting the TACKY must give the same exit status.
+#[test]
+fn matches_native_execution() {
+    for program in programs() {
+        let source = scratch_copy("interpreter", &program);
+        let status = Command::new(WACC).arg("--interpret").arg(&source).status().expect("That `wacc` should be executed");
+        assert_eq!(status.code().unwrap(), reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
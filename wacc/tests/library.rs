@@ -0,0 +1,54 @@
+use wacc::ast_nodes::*;
+use wacc::{compile_str, Options, Severity, Target, TargetProgram, Warning};
+
+#[test]
+fn artifacts() {
+    let mut options = Options::default();
+    options.set_filename("negate.c");
+    let artifacts = compile_str("int main(void) {\n    return -(~2);\n}\n", options).expect("That the program should compile");
+
+    let tokens: Vec<&str> = artifacts.tokens.iter().map(|(token, _)| token.as_str()).collect();
+    assert_eq!(tokens, ["int", "main", "(", "void", ")", "{", "return", "-", "(", "~", "2", ")", ";", "}"]);
+    assert_eq!(artifacts.tokens[7].1.to_string(), "negate.c:2:12");
+    assert_eq!(artifacts.ast.to_string(), "int main(void) {\n    return -(~2);\n}\n");
+    assert_eq!(artifacts.tacky.to_string(), "function main:\n    tmp0 = ~2\n    tmp1 = -tmp0\n    return tmp1\n");
+    assert!(matches!(artifacts.target_program, TargetProgram::X86_64(asm::Program(_))));
+    assert!(artifacts.assembly.contains("main:"), "{}", artifacts.assembly);
+    assert!(artifacts.warnings.is_empty());
+}
+
+#[test]
+fn targets() {
+    let mut options = Options::default();
+    options.set_target(Target::Wasm32);
+    let artifacts = compile_str("int main(void) { return 2; }", options).expect("That the program should compile");
+    assert!(matches!(artifacts.target_program, TargetProgram::Wasm32(_)));
+    assert!(artifacts.assembly.starts_with("(module"), "{}", artifacts.assembly);
+}
+
+#[test]
+fn diagnostics() {
+    let errors = compile_str("int main(void) { return 2 }", Options::default()).expect_err("That the program should not compile");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "expected `;`, found `}`");
+    assert_eq!(errors[0].primary().unwrap().span.to_string(), "<source>:1:27");
+
+    let mut options = Options::default();
+    options.get_warning_options_mut().set(Warning::Conversion, true);
+    let artifacts = compile_str("int main(void) { return 4294967295; }", options.clone()).unwrap();
+    assert_eq!(artifacts.warnings.len(), 1);
+    assert_eq!(artifacts.warnings[0].severity, Severity::Warning);
+
+    options.get_warning_options_mut().set(Warning::Conversion, false);
+    assert!(compile_str("int main(void) { return 4294967295; }", options).unwrap().warnings.is_empty());
+    assert!(compile_str("int main(void) { return 4294967295; }", Options::default()).unwrap().warnings.is_empty());
+}
+
+// Non-ASCII characters are reported like any invalid token, with a span.
+#[test]
+fn non_ascii() {
+    let errors = compile_str("int main(void) { return ½; }", Options::default()).expect_err("That `½` should be an invalid token");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "invalid token `½`");
+    assert_eq!(errors[0].primary().unwrap().span.to_string(), "<source>:1:25");
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use common::{programs, reference_exit_code, run, scratch_copy, wacc};
+
+const ET_EXEC: u8 = 2;
+
+fn wacc_output(args: &[&str], scratch: &Path) -> Output {
+    Command::new(common::WACC).args(args).current_dir(scratch).output().expect("That `wacc` should be executed")
+}
+
+// With no toolchain on the `PATH`, every program still builds into a static executable.
+#[test]
+fn without_toolchain() {
+    for program in programs() {
+        let source = scratch_copy("linker-toolchain", &program);
+        let empty_path = source.with_file_name("bin");
+        fs::create_dir(&empty_path).unwrap();
+        let output = Command::new(common::WACC).arg("-v").arg(&source).env("PATH", &empty_path)
+            .output().expect("That `wacc` should be executed");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let executable = source.with_extension("");
+        assert_eq!(fs::read(&executable).unwrap()[16], ET_EXEC, "{}", program.display());
+        assert_eq!(run(executable.to_str().unwrap(), &[] as &[&str]), reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
+
+// Objects from gas link as well as the integrated assembler's.
+#[test]
+fn gas_objects() {
+    let source = scratch_copy("linker-gas", &Path::new(common::PROGRAMS_DIR).join("nested_unary.c"));
+    let scratch = source.parent().unwrap();
+    wacc(&["-c", "-fno-integrated-as"], &source);
+    let output = wacc_output(&["-v", "nested_unary.o", "-o", "program"], scratch);
+    assert!(output.status.success() && output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(run(scratch.join("program").to_str().unwrap(), &[] as &[&str]), reference_exit_code(&source));
+    fs::remove_dir_all(scratch).ok();
+}
+
+#[test]
+fn falls_back_to_gcc() {
+    let source = scratch_copy("linker-fallback", &Path::new(common::PROGRAMS_DIR).join("return_constant.c"));
+    let scratch = source.parent().unwrap();
+    let expected = reference_exit_code(&source);
+    fs::write(scratch.join("helper.c"), "#include <stdio.h>\nint helper(void) { return puts(\"helper\"); }\n").unwrap();
+    assert!(Command::new("gcc").args(["-c", "helper.c"]).current_dir(scratch).status().unwrap().success());
+
+    // The helper needs libc.
+    wacc(&["-c"], &source);
+    let output = wacc_output(&["-v", "return_constant.o", "helper.o", "-o", "program"], scratch);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("Linking with gcc: `helper.o`: ") && stderr.contains("\ngcc "), "{stderr}");
+    assert_eq!(run(scratch.join("program").to_str().unwrap(), &[] as &[&str]), expected);
+
+    // So do libraries, and `-fuse-ld=gcc` asks for gcc.
+    for args in [&["-lm"][..], &["-fuse-ld=gcc"]] {
+        let output = wacc_output(&[args, &["-v", "return_constant.c", "-o", "program"]].concat(), scratch);
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("gcc "), "{args:?}");
+        assert_eq!(run(scratch.join("program").to_str().unwrap(), &[] as &[&str]), expected);
+    }
+    fs::remove_dir_all(scratch).ok();
+}
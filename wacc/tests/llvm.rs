@@ -0,0 +1,67 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{check_golden, programs, reference_exit_code, scratch_copy, wacc};
+
+#[test]
+fn golden_module() {
+    let source = scratch_copy("llvm-golden", Path::new("tests/programs/nested_unary.c"));
+    wacc(&["--emit-llvm"], &source);
+    let actual = fs::read_to_string(source.with_extension("ll")).expect("That `wacc --emit-llvm` should write LLVM IR");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    // The module records the source path, which differs between scratch directories.
+    let actual = actual.replace(source.to_str().unwrap(), "nested_unary.c");
+    check_golden(&actual, Path::new("tests/golden/llvm/nested_unary.ll"));
+}
+
+// TACKY variables named `v0` or `entry` don't clash with the values and the block of the function.
+#[test]
+fn colliding_names() {
+    let source = scratch_copy("llvm-collisions", Path::new("tests/golden/llvm/collisions.tacky"));
+    wacc(&["--from-tacky", "--emit-llvm"], &source);
+    let actual = fs::read_to_string(source.with_extension("ll")).expect("That `wacc --emit-llvm` should write LLVM IR");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    let actual = actual.replace(source.to_str().unwrap(), "collisions.tacky");
+    check_golden(&actual, Path::new("tests/golden/llvm/collisions.ll"));
+}
+
+// The source file name is an LLVM string constant, whatever characters it has.
+#[test]
+fn source_filename_escapes() {
+    let source = scratch_copy("llvm-file-name", Path::new("tests/programs/return_constant.c"));
+    let renamed = source.with_file_name("quote\"back\\slash\u{fc}.c");
+    fs::rename(&source, &renamed).unwrap();
+    wacc(&["--emit-llvm"], &renamed);
+    let actual = fs::read_to_string(renamed.with_extension("ll")).expect("That `wacc --emit-llvm` should write LLVM IR");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    assert!(actual.contains("quote\\22back\\5Cslash\\C3\\BC.c\"\n"), "{actual}");
+}
+
+// LLVM before 15 only accepts the `ptr` type with `-opaque-pointers`, which later releases removed.
+fn lli(module: &Path) -> i32 {
+    let output = Command::new("lli").arg(module).output().expect("That `lli` should be executed");
+    if String::from_utf8_lossy(&output.stderr).contains("-opaque-pointers") {
+        let status = Command::new("lli").arg("-opaque-pointers").arg(module).status().expect("That `lli` should be executed");
+        return status.code().unwrap();
+    }
+    output.status.code().unwrap()
+}
+
+#[test]
+#[ignore = "needs LLVM's `lli`"]
+fn run_under_lli() {
+    for program in programs() {
+        let source = scratch_copy("llvm-lli", &program);
+        wacc(&["--emit-llvm"], &source);
+        assert_eq!(lli(&source.with_extension("ll")), reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+
+    // -(~(-5)) = -4
+    let source = scratch_copy("llvm-lli-collisions", Path::new("tests/golden/llvm/collisions.tacky"));
+    wacc(&["--from-tacky", "--emit-llvm"], &source);
+    assert_eq!(lli(&source.with_extension("ll")), 252);
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{check_golden, programs, reference_exit_code, run, scratch_copy, wacc};
+
+const GOLDEN_DIR: &str = "tests/golden/optimizer";
+
+// C programs are a single `return` so far, without any dead store, which is why the input is TACKY.
+#[test]
+fn dead_stores() {
+    let source = scratch_copy("optimizer-dead-stores", &Path::new(GOLDEN_DIR).join("dead_stores.tacky"));
+    let dump = source.with_file_name("dump.tacky");
+    for args in [&["-O"][..], &["--eliminate-dead-stores"]] {
+        wacc(&[args, &["--from-tacky", &format!("--dump-tacky={}", dump.display())]].concat(), &source);
+        check_golden(&fs::read_to_string(&dump).unwrap(), &Path::new(GOLDEN_DIR).join("dead_stores.optimized.tacky"));
+        // ~(-5) = 4
+        assert_eq!(run(source.with_extension("").to_str().unwrap(), &[] as &[&str]), 4, "{args:?}");
+    }
+    // The same as without the optimization.
+    wacc(&["--from-tacky"], &source);
+    assert_eq!(run(source.with_extension("").to_str().unwrap(), &[] as &[&str]), 4);
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
+
+#[test]
+fn optimized_programs_match_gcc() {
+    for program in programs() {
+        let source = scratch_copy("optimizer-programs", &program);
+        wacc(&["-O"], &source);
+        let status = run(source.with_extension("").to_str().unwrap(), &[] as &[&str]);
+        assert_eq!(status, reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
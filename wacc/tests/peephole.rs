@@ -0,0 +1,25 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{check_golden, scratch_copy, wacc};
+
+const GOLDEN_DIR: &str = "tests/golden/peephole";
+
+fn check_peephole_golden(name: &str) {
+    let source = scratch_copy(&format!("peephole-{name}"), &Path::new(GOLDEN_DIR).join(format!("{name}.c")));
+    wacc(&["-S", "--peephole"], &source);
+    let actual = fs::read_to_string(source.with_extension("s")).expect("That `wacc -S` should write assembly");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&actual, &Path::new(GOLDEN_DIR).join(format!("{name}.s")));
+}
+
+#[test]
+fn return_zero() {
+    check_peephole_golden("return_zero");
+}
+
+#[test]
+fn nested_unary() {
+    check_peephole_golden("nested_unary");
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{check_golden, reference_exit_code, run, scratch_copy, wacc, wacc_error};
+
+const GOLDEN_DIR: &str = "tests/golden/preprocessor";
+
+fn include_dir() -> String {
+    Path::new(GOLDEN_DIR).join("include").canonicalize().unwrap().display().to_string()
+}
+
+#[test]
+fn macros() {
+    let source = scratch_copy("preprocessor-macros", &Path::new(GOLDEN_DIR).join("macros.c"));
+    wacc(&["-E", "-I", &include_dir()], &source);
+    let actual = fs::read_to_string(source.with_extension("i")).expect("That `wacc -E` should write the preprocessed source");
+    // `__FILE__` and the line markers hold absolute paths.
+    let actual = actual.replace(source.to_str().unwrap(), "macros.c").replace(&include_dir(), "include");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&actual, &Path::new(GOLDEN_DIR).join("macros.i"));
+}
+
+#[test]
+fn run_matches_gcc() {
+    // With the header next to the source, `#include "..."` finds it without `-I`, for gcc as well.
+    let source = scratch_copy("preprocessor-run", &Path::new(GOLDEN_DIR).join("run.c"));
+    fs::copy(Path::new(GOLDEN_DIR).join("include/macros.h"), source.with_file_name("macros.h"))
+        .expect("That the header should be copied");
+    wacc(&[], &source);
+    let status = run(source.with_extension("").to_str().unwrap(), &[] as &[&str]);
+    assert_eq!(status, reference_exit_code(&source));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
+
+// Short-circuiting operators and unsigned arithmetic in `#if`.
+#[test]
+fn conditions_match_gcc() {
+    let source = scratch_copy("preprocessor-conditions", &Path::new(GOLDEN_DIR).join("conditions.c"));
+    wacc(&[], &source);
+    let status = run(source.with_extension("").to_str().unwrap(), &[] as &[&str]);
+    assert_eq!(status, reference_exit_code(&source));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
+
+// Line markers and `__FILE__` escape the presumed file name as a C string, which the lexer reads back.
+#[test]
+fn file_names() {
+    let source = scratch_copy("preprocessor-file-names", &Path::new(GOLDEN_DIR).join("file_names.c"));
+    wacc(&["-E"], &source);
+    let preprocessed = fs::read_to_string(source.with_extension("i")).unwrap();
+    let literal = r#""tab\011here \303\274 \"q\".c""#;
+    assert!(preprocessed.contains(&format!("# 10 {literal}\n{literal}\n")), "{preprocessed}");
+    let stderr = wacc_error(&["-S"], &source);
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    assert!(stderr.contains("tab\there \u{fc} \"q\".c:10:1"), "{stderr}");
+}
+
+fn check_failure(name: &str, expected_error: &str) {
+    let source = scratch_copy(&format!("preprocessor-{name}"), &Path::new(GOLDEN_DIR).join(format!("{name}.c")));
+    let stderr = wacc_error(&[], &source);
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    assert!(stderr.contains(expected_error), "unexpected error:\n{stderr}");
+}
+
+#[test]
+fn condition_error() {
+    check_failure("condition_error", "condition_error.c:2: Division by zero in `#if` expression");
+}
+
+#[test]
+fn directive_error() {
+    check_failure("directive_error", "directive_error.c:2: #error UNDEFINED is not defined");
+}
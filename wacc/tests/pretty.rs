@@ -0,0 +1,53 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{check_golden, programs, scratch_copy, wacc};
+
+const GOLDEN_DIR: &str = "tests/golden/pretty";
+
+// Runs `wacc` on `source` with `--dump-<name>` and returns the dump.
+fn dump(name: &str, args: &[&str], source: &Path) -> String {
+    let path = source.with_extension(name);
+    wacc(&[args, &[&format!("--dump-{name}={}", path.display())]].concat(), source);
+    fs::read_to_string(path).expect("That the dump should be written")
+}
+
+fn check_dump_golden(program: &Path, name: &str, args: &[&str]) {
+    let source = scratch_copy(&format!("pretty-{name}"), program);
+    let actual = dump(name, args, &source);
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    let stem = program.file_stem().unwrap().to_str().unwrap();
+    check_golden(&actual, &Path::new(GOLDEN_DIR).join(format!("{stem}.{name}")));
+}
+
+#[test]
+fn c_source() {
+    check_dump_golden(Path::new("tests/programs/nested_unary.c"), "ast", &["--parse"]);
+    check_dump_golden(&Path::new(GOLDEN_DIR).join("spacing.c"), "ast", &["--parse"]);
+}
+
+#[test]
+fn tacky() {
+    check_dump_golden(Path::new("tests/programs/nested_unary.c"), "tacky", &["--tacky"]);
+}
+
+#[test]
+fn asm() {
+    check_dump_golden(Path::new("tests/programs/nested_unary.c"), "asm", &["--codegen"]);
+}
+
+// Printing the AST gives C that parses back to the same tree, and so to the same TACKY.
+#[test]
+fn c_round_trip() {
+    let spacing = Path::new(GOLDEN_DIR).join("spacing.c");
+    for program in programs().iter().chain([&spacing]) {
+        let source = scratch_copy("pretty-round-trip", program);
+        let printed = source.with_file_name("printed.c");
+        fs::write(&printed, dump("ast", &["--parse"], &source)).unwrap();
+
+        assert_eq!(dump("ast", &["--parse"], &printed), fs::read_to_string(&printed).unwrap(), "{}", program.display());
+        assert_eq!(dump("tacky", &["--tacky"], &printed), dump("tacky", &["--tacky"], &source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use common::{check_golden, run, scratch_copy, wacc, WACC};
+
+const GOLDEN_DIR: &str = "tests/golden/regalloc";
+
+// The asm `--from-tacky` allocates for the TACKY program `name`, checked against its golden.
+fn check_allocation(name: &str) -> PathBuf {
+    let source = scratch_copy(&format!("regalloc-{name}"), &Path::new(GOLDEN_DIR).join(format!("{name}.tacky")));
+    let asm = source.with_extension("asm");
+    wacc(&["--from-tacky", &format!("--dump-asm={}", asm.display())], &source);
+    check_golden(&fs::read_to_string(&asm).unwrap(), &Path::new(GOLDEN_DIR).join(format!("{name}.asm")));
+    source
+}
+
+fn simulate(source: &Path) -> Option<i32> {
+    let output = Command::new(WACC).args(["--from-tacky", "--simulate"]).arg(source)
+        .output().expect("That `wacc` should be executed");
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    output.status.code()
+}
+
+// The golden has a single `mov`, of the constant.
+#[test]
+fn coalescing() {
+    let source = check_allocation("chain");
+    // -(~(-(-5))) = 6
+    assert_eq!(run(source.with_extension("").to_str().unwrap(), &[] as &[&str]), 6);
+    assert_eq!(simulate(&source), Some(6));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
+
+// The golden spills some temporaries to the frame and pushes every callee-saved register.
+#[test]
+fn register_pressure() {
+    let source = check_allocation("pressure");
+    assert_eq!(run(source.with_extension("").to_str().unwrap(), &[] as &[&str]), 255);
+    // The simulator fails if a callee-saved register isn't restored.
+    assert_eq!(simulate(&source), Some(255));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
+
+// The callee-saved registers hold the caller's values again once the function returns.
+#[test]
+fn callee_saved_registers_restored() {
+    let source = scratch_copy("regalloc-callee-saved", &Path::new(GOLDEN_DIR).join("pressure.tacky"));
+    let scratch = source.parent().unwrap();
+    fs::copy(Path::new(GOLDEN_DIR).join("harness.s"), scratch.join("harness.s")).unwrap();
+    wacc(&["--from-tacky", "-c", "--symbol-prefix", "wacc_"], &source);
+    let linked = Command::new("gcc").args(["harness.s", "pressure.o", "-o", "program"]).current_dir(scratch)
+        .status().expect("That gcc should be executed");
+    assert!(linked.success());
+    assert_eq!(run(scratch.join("program").to_str().unwrap(), &[] as &[&str]), 255);
+    fs::remove_dir_all(scratch).ok();
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{check_golden, programs, reference_exit_code, run, scratch_copy, wacc};
+
+const TARGET: &str = "riscv64-linux-gnu";
+
+#[test]
+fn golden_assembly() {
+    let source = scratch_copy("riscv64-golden", Path::new("tests/programs/large_constant.c"));
+    wacc(&["--target", TARGET, "-S"], &source);
+    let actual = fs::read_to_string(source.with_extension("s")).expect("That `wacc -S` should write assembly");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&actual, Path::new("tests/golden/riscv64/large_constant.s"));
+}
+
+#[test]
+#[ignore = "needs the `riscv64-linux-gnu-gcc` cross toolchain and `qemu-riscv64`"]
+fn run_under_qemu() {
+    for program in programs() {
+        let source = scratch_copy("riscv64-qemu", &program);
+        wacc(&["--target", TARGET], &source);
+        let executable = source.with_extension("");
+        let code = run("qemu-riscv64", &["-L".as_ref(), "/usr/riscv64-linux-gnu".as_ref(), executable.as_os_str()]);
+        assert_eq!(code, reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+use common::{programs, reference_exit_code, scratch_copy, WACC};
+
+fn check_simulation(args: &[&str]) {
+    for program in programs() {
+        let source = scratch_copy("simulator", &program);
+        let status = Command::new(WACC).arg("--simulate").args(args).arg(&source).status().expect("That `wacc` should be executed");
+        assert_eq!(status.code().unwrap() & 0xff, reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
+
+#[test]
+fn matches_native_execution() {
+    check_simulation(&[]);
+}
+
+#[test]
+fn matches_native_execution_optimized() {
+    check_simulation(&["-O"]);
+}
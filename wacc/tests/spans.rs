@@ -0,0 +1,47 @@
+mod common;
+
+use std::fs;
+use common::wacc_error;
+
+const GOLDEN_DIR: &str = "tests/golden/spans";
+
+// Copies the whole directory, since some sources include headers next to them.
+fn check_error_location(name: &str, expected_location: &str, expected_message: &str) {
+    let scratch = std::env::temp_dir().join(format!("wacc-spans-{name}-{}", std::process::id()));
+    fs::create_dir_all(&scratch).expect("That the scratch directory should be created");
+    for entry in fs::read_dir(GOLDEN_DIR).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, scratch.join(path.file_name().unwrap())).expect("That the source should be copied");
+    }
+    let stderr = wacc_error(&[], &scratch.join(format!("{name}.c")));
+    let stderr = stderr.replace(&format!("{}/", scratch.display()), "");
+    fs::remove_dir_all(&scratch).ok();
+    assert!(stderr.contains(&format!("--> {expected_location}\n")), "unexpected location:\n{stderr}");
+    assert!(stderr.contains(expected_message), "unexpected error:\n{stderr}");
+}
+
+#[test]
+fn missing_semicolon() {
+    check_error_location("missing_semicolon", "missing_semicolon.c:3:1", "expected `;`, found `}`");
+}
+
+#[test]
+fn error_in_header() {
+    check_error_location("header_error", "body.h:1:14", "expected `;`, found `2`");
+}
+
+#[test]
+fn error_after_header() {
+    check_error_location("after_header", "after_header.c:4:1", "expected `;`, found `}`");
+}
+
+#[test]
+fn line_directive() {
+    check_error_location("line_directive", "generated.c:100:12", "invalid token `$`");
+}
+
+
+#[test]
+fn gcc_line_markers() {
+    check_error_location("gcc_line_marker", "gcc_line_marker.c:3:12", "invalid token `$`");
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{check_golden, programs, run, scratch_copy, wacc, wacc_error, WACC};
+
+const GOLDEN_DIR: &str = "tests/golden/tacky";
+
+fn dump_tacky(args: &[&str], source: &Path) -> String {
+    let path = source.with_file_name("dump.tacky");
+    wacc(&[args, &["--tacky", &format!("--dump-tacky={}", path.display())]].concat(), source);
+    fs::read_to_string(path).expect("That the TACKY dump should be written")
+}
+
+// The TACKY printed for every program parses back to the same program.
+#[test]
+fn round_trip() {
+    for program in programs() {
+        let source = scratch_copy("tacky-round-trip", &program);
+        let printed = dump_tacky(&[], &source);
+        let copy = source.with_extension("tacky");
+        fs::write(&copy, &printed).unwrap();
+        assert_eq!(dump_tacky(&["--from-tacky"], &copy), printed, "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}
+
+#[test]
+fn backend() {
+    let source = scratch_copy("tacky-backend", &Path::new(GOLDEN_DIR).join("reuse.tacky"));
+    let asm = source.with_extension("asm");
+    wacc(&["--from-tacky", &format!("--dump-asm={}", asm.display())], &source);
+    check_golden(&fs::read_to_string(&asm).unwrap(), &Path::new(GOLDEN_DIR).join("reuse.asm"));
+
+    // `d` is ~(-(-5)) = -6, which exits as 250.
+    assert_eq!(run(source.with_extension("").to_str().unwrap(), &[] as &[&str]), 250);
+    let interpreted = Command::new(WACC).args(["--from-tacky", "--interpret"]).arg(&source)
+        .status().expect("That `wacc` should be executed");
+    assert_eq!(interpreted.code(), Some(250));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+}
+
+fn check_errors(name: &str) {
+    let source = scratch_copy(&format!("tacky-{name}"), &Path::new(GOLDEN_DIR).join(format!("{name}.tacky")));
+    let stderr = wacc_error(&["--from-tacky"], &source).replace(source.to_str().unwrap(), &format!("{name}.tacky"));
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&stderr, &Path::new(GOLDEN_DIR).join(format!("{name}.stderr")));
+}
+
+#[test]
+fn errors() {
+    check_errors("errors");
+}
+
+// Falling off the end of the function would run whatever follows it.
+#[test]
+fn no_return() {
+    check_errors("no_return");
+}
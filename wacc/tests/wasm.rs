@@ -0,0 +1,31 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{check_golden, programs, reference_exit_code, scratch_copy, wacc};
+
+const TARGET: &str = "wasm32-unknown-unknown";
+
+#[test]
+fn golden_module() {
+    let source = scratch_copy("wasm-golden", Path::new("tests/programs/nested_unary.c"));
+    wacc(&["--target", TARGET], &source);
+    let actual = fs::read_to_string(source.with_extension("wat")).expect("That `wacc` should write a wasm module");
+    fs::remove_dir_all(source.parent().unwrap()).ok();
+    check_golden(&actual, Path::new("tests/golden/wasm/nested_unary.wat"));
+}
+
+#[test]
+#[ignore = "needs the `wasmtime` runtime"]
+fn run_under_wasmtime() {
+    for program in programs() {
+        let source = scratch_copy("wasm-wasmtime", &program);
+        wacc(&["--target", TARGET], &source);
+        let output = Command::new("wasmtime").args(["--invoke", "main"]).arg(source.with_extension("wat")).output()
+            .expect("That `wasmtime` should be executed");
+        let result: i32 = String::from_utf8_lossy(&output.stdout).trim().parse().expect("That `main` should return an i32");
+        assert_eq!(result & 0xff, reference_exit_code(&source), "{}", program.display());
+        fs::remove_dir_all(source.parent().unwrap()).ok();
+    }
+}